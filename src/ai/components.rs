@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+// Everything that changes how an AI robot behaves, set per archetype in its .enemy.ron file.
+// Anything left out of the file uses the default below.
+#[derive(Component, Debug, Clone, Copy, Deserialize, Reflect)]
+#[serde(default)]
+pub struct AiTuning {
+    #[serde(skip)]
+    pub max_speed: f32, // filled in from the archetype's speed
+    pub max_force: f32,         // the most steering acceleration it can put out
+    pub attack_range: f32,      // close enough to stop chasing and attack
+    pub keep_distance: f32,     // attacks from this far away, 0.0 charges straight in to ram
+    pub retreat_health: f32,    // runs away below this fraction of its starting health
+    pub slowing_radius: f32,    // starts braking this far from where it's heading
+    pub patrol_radius: f32,     // how far it wanders from where it spawned
+    pub idle_time: f32,         // seconds it stands still between patrols
+    pub patrol_time: f32,       // seconds it patrols before having a rest
+    pub wander_jitter: f32,     // how quickly its wandering direction changes, radians per second
+    pub separation_radius: f32, // tries to keep at least this far from other AI robots
+    pub separation_weight: f32,
+}
+
+impl Default for AiTuning {
+    fn default() -> Self {
+        Self {
+            max_speed: 3.0,
+            max_force: 6.0,
+            attack_range: 3.0,
+            keep_distance: 0.0,
+            retreat_health: 0.25,
+            slowing_radius: 2.0,
+            patrol_radius: 5.0,
+            idle_time: 2.0,
+            patrol_time: 5.0,
+            wander_jitter: 2.0,
+            separation_radius: 1.5,
+            separation_weight: 1.5,
+        }
+    }
+}
+
+// What an AI robot can see and hear. It only knows about targets it has perceived, and remembers
+// where it last saw or heard them for a while after they disappear. Set per archetype in its
+// .enemy.ron file.
+#[derive(Component, Debug, Clone, Copy, Deserialize, Reflect)]
+#[serde(default)]
+pub struct Perception {
+    pub view_distance: f32,  // can't see anything further away than this
+    pub view_angle: f32,     // how wide its view cone is, in degrees
+    pub hearing_radius: f32, // hears noises this close even when it can't see them
+    pub memory_time: f32,    // seconds it remembers where a target was after losing it
+}
+
+impl Default for Perception {
+    fn default() -> Self {
+        Self {
+            view_distance: 12.0,
+            view_angle: 120.0,
+            hearing_radius: 8.0,
+            memory_time: 5.0,
+        }
+    }
+}
+
+impl Perception {
+    // whether a point at offset from the robot is inside its view cone, facing is the way it looks
+    pub fn in_view(&self, facing: Vec3, offset: Vec3) -> bool {
+        let facing = Vec3::new(facing.x, 0.0, facing.z);
+        let offset = Vec3::new(offset.x, 0.0, offset.z);
+        if offset.length() > self.view_distance {
+            return false;
+        }
+        // right on top of it counts as seen
+        offset.length_squared() <= f32::EPSILON
+            || facing.angle_between(offset) <= (self.view_angle * 0.5).to_radians()
+    }
+}
+
+// How a swarm robot flocks, see steer_swarm. Swarms are meant to come in the hundreds so they skip
+// the state machine and just pile towards the nearest target together.
+#[derive(Component, Debug, Clone, Copy, Deserialize, Reflect)]
+#[serde(default)]
+pub struct SwarmTuning {
+    #[serde(skip)]
+    pub max_speed: f32, // filled in from the archetype's speed
+    pub max_force: f32,
+    pub sight_range: f32,       // how far away it notices a target
+    pub neighbour_radius: f32,  // swarm robots closer than this count as its flock
+    pub separation_radius: f32, // tries to keep at least this far from its flockmates
+    pub cohesion_weight: f32,   // how much it heads for the middle of its flock
+    pub alignment_weight: f32,  // how much it matches its flock's heading
+    pub separation_weight: f32, // how much it avoids bumping into its flock
+    pub seek_weight: f32,       // how much it heads for the target
+}
+
+impl Default for SwarmTuning {
+    fn default() -> Self {
+        Self {
+            max_speed: 5.0,
+            max_force: 10.0,
+            sight_range: 30.0,
+            neighbour_radius: 2.5,
+            separation_radius: 0.8,
+            cohesion_weight: 0.6,
+            alignment_weight: 1.0,
+            separation_weight: 2.5,
+            seek_weight: 1.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum AiState {
+    Idle,
+    Patrol,
+    Chase,
+    Attack,
+    Retreat,
+    Investigate, // lost sight of its target, goes to look where it last saw or heard it
+}
+
+impl AiState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AiState::Idle => "Idle",
+            AiState::Patrol => "Patrol",
+            AiState::Chase => "Chase",
+            AiState::Attack => "Attack",
+            AiState::Retreat => "Retreat",
+            AiState::Investigate => "Investigate",
+        }
+    }
+}
+
+// The AI's state machine and what it remembers between frames
+#[derive(Component, Debug, Reflect)]
+pub struct AiBrain {
+    pub state: AiState,
+    pub time_in_state: f32,
+    pub home: Vec3,               // where it spawned, patrols stay around here
+    pub max_health: f32,          // its health when it spawned, used to decide when to retreat
+    pub wander_angle: f32,        // which way it's currently wandering, radians around y
+    pub target: Option<Entity>,   // the target it can see right now
+    pub last_known: Option<Vec3>, // where it last saw or heard a target
+    pub memory: f32,              // seconds until it forgets last_known
+}
+
+impl AiBrain {
+    pub fn new(home: Vec3, max_health: f32) -> Self {
+        Self {
+            state: AiState::Idle,
+            time_in_state: 0.0,
+            home,
+            max_health,
+            wander_angle: 0.0,
+            target: None,
+            last_known: None,
+            memory: 0.0,
+        }
+    }
+
+    pub fn set_state(&mut self, state: AiState) {
+        if self.state != state {
+            self.state = state;
+            self.time_in_state = 0.0;
+        }
+    }
+}
@@ -0,0 +1,8 @@
+mod components;
+mod plugins;
+mod steering;
+mod systems;
+
+pub use components::*;
+pub use plugins::*;
+pub use steering::*;
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+use crate::{schedule::InGameSet, settings::dev_tools_enabled};
+
+use super::systems::*;
+use super::{AiBrain, AiTuning, Perception, SwarmTuning};
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        // the AI is the enemies' version of the player's controls, so it runs with the user input
+        app.register_type::<AiBrain>()
+            .register_type::<AiTuning>()
+            .register_type::<SwarmTuning>()
+            .register_type::<Perception>()
+            .add_systems(
+                Update,
+                (
+                    attach_ai_brains,
+                    perceive_targets,
+                    update_ai_state,
+                    steer_ai,
+                    steer_swarm,
+                    ai_fire_weapons,
+                )
+                    .chain()
+                    .in_set(InGameSet::UserInput),
+            )
+            .add_systems(
+                Update,
+                draw_perception
+                    .run_if(dev_tools_enabled)
+                    .after(InGameSet::EntityUpdates),
+            );
+    }
+}
@@ -0,0 +1,138 @@
+// Steering behaviours (see Craig Reynolds' "Steering Behaviors For Autonomous Characters").
+// Each one works out the velocity the robot would like to have and returns the steering force
+// needed to get there from the velocity it has now. Forces from several behaviours can be added
+// up, and the result goes into the robot's Acceleration. Everything happens on the ground, y is
+// always ignored.
+use bevy::prelude::*;
+
+fn flat(vector: Vec3) -> Vec3 {
+    Vec3::new(vector.x, 0.0, vector.z)
+}
+
+// head straight for the target at full speed
+pub fn seek(position: Vec3, velocity: Vec3, target: Vec3, max_speed: f32) -> Vec3 {
+    let desired = flat(target - position).normalize_or_zero() * max_speed;
+    desired - flat(velocity)
+}
+
+// head straight away from the target at full speed
+pub fn flee(position: Vec3, velocity: Vec3, target: Vec3, max_speed: f32) -> Vec3 {
+    let desired = flat(position - target).normalize_or_zero() * max_speed;
+    desired - flat(velocity)
+}
+
+// like seek but slows down inside slowing_radius so it stops on the target instead of overshooting
+pub fn arrive(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    max_speed: f32,
+    slowing_radius: f32,
+) -> Vec3 {
+    let offset = flat(target - position);
+    let distance = offset.length();
+    let speed = if distance < slowing_radius {
+        max_speed * distance / slowing_radius.max(f32::EPSILON)
+    } else {
+        max_speed
+    };
+    offset.normalize_or_zero() * speed - flat(velocity)
+}
+
+// how long it takes to get to a moving target, used to aim at where it's going to be
+fn prediction_time(position: Vec3, target: Vec3, max_speed: f32) -> f32 {
+    flat(target - position).length() / max_speed.max(f32::EPSILON)
+}
+
+// seek where a moving target is going to be rather than where it is now
+pub fn pursue(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    max_speed: f32,
+) -> Vec3 {
+    let ahead = prediction_time(position, target, max_speed);
+    seek(
+        position,
+        velocity,
+        target + target_velocity * ahead,
+        max_speed,
+    )
+}
+
+// flee from where a moving target is going to be
+pub fn evade(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    max_speed: f32,
+) -> Vec3 {
+    let ahead = prediction_time(position, target, max_speed);
+    flee(
+        position,
+        velocity,
+        target + target_velocity * ahead,
+        max_speed,
+    )
+}
+
+// Drifts about without a goal. The wander angle is nudged by a random amount up to jitter every
+// call, so the robot curves around smoothly instead of jittering on the spot.
+pub fn wander(
+    velocity: Vec3,
+    wander_angle: &mut f32,
+    jitter: f32,
+    max_speed: f32,
+    rng: &mut impl rand::Rng,
+) -> Vec3 {
+    *wander_angle += rng.gen_range(-jitter..=jitter);
+    let desired = Vec3::new(wander_angle.sin(), 0.0, wander_angle.cos()) * max_speed * 0.5;
+    desired - flat(velocity)
+}
+
+// pushes away from every neighbour closer than radius, harder the closer they are
+pub fn separation(position: Vec3, neighbours: impl Iterator<Item = Vec3>, radius: f32) -> Vec3 {
+    let mut force = Vec3::ZERO;
+    for neighbour in neighbours {
+        let offset = flat(position - neighbour);
+        let distance = offset.length();
+        if distance > f32::EPSILON && distance < radius {
+            force += offset / distance * (radius - distance) / radius;
+        }
+    }
+    force
+}
+
+// The flocking behaviours, from Reynolds' boids. Each one only looks at the robot's flock, the
+// flockmates close enough for it to notice.
+
+// head for the middle of the flock
+pub fn cohesion(
+    position: Vec3,
+    velocity: Vec3,
+    neighbours: impl Iterator<Item = Vec3>,
+    max_speed: f32,
+) -> Vec3 {
+    let (total, count) = neighbours.fold((Vec3::ZERO, 0), |(total, count), neighbour| {
+        (total + neighbour, count + 1)
+    });
+    if count == 0 {
+        return Vec3::ZERO;
+    }
+    seek(position, velocity, total / count as f32, max_speed)
+}
+
+// match the flock's average heading
+pub fn alignment(
+    velocity: Vec3,
+    neighbour_velocities: impl Iterator<Item = Vec3>,
+    max_speed: f32,
+) -> Vec3 {
+    let heading = neighbour_velocities.fold(Vec3::ZERO, |total, neighbour| total + flat(neighbour));
+    if heading == Vec3::ZERO {
+        return Vec3::ZERO;
+    }
+    heading.normalize() * max_speed - flat(velocity)
+}
@@ -0,0 +1,582 @@
+use bevy::prelude::*;
+
+use bevy_rapier3d::{plugin::RapierContext, prelude::QueryFilter};
+
+use crate::{
+    archetypes::AiProfile,
+    combat::{fire_weapon, lead_target, Health, NoiseEvent, StatusEffects, Team, Weapons},
+    entities::Bullet,
+    movement::{Acceleration, Velocity},
+    navigation::{FollowPath, PathRequest, PathTask},
+    prefabs::{PrefabRegistry, PrefabSpawner},
+    spatial::SpatialHash,
+};
+
+use super::*;
+
+// how quickly a robot turns on the spot when it's looking for a target it lost, radians per second
+const LOOK_AROUND_SPEED: f32 = 1.5;
+
+// gives a brain to anything spawned with the steering AI profile, it remembers where it spawned
+// and how much health it started with. Swarm robots don't need a brain, just their tuning.
+pub fn attach_ai_brains(
+    mut commands: Commands,
+    query: Query<(Entity, &AiProfile, &Transform, &Health), Added<AiProfile>>,
+) {
+    for (entity, profile, transform, health) in query.iter() {
+        match profile {
+            AiProfile::Steering(tuning) => {
+                commands
+                    .entity(entity)
+                    .insert((AiBrain::new(transform.translation, health.value), *tuning));
+            }
+            AiProfile::Swarm(tuning) => {
+                commands.entity(entity).insert(*tuning);
+            }
+            AiProfile::Stationary => (),
+        }
+    }
+}
+
+// Works out what each AI robot knows about. It sees the closest robot on another team that's
+// inside its view cone with nothing in the way, and hears shots fired close enough by. Either one
+// refreshes its memory of where the target is, otherwise the memory fades after a while. Hiding
+// behind cover or sneaking up from behind really works.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn perceive_targets(
+    mut ai_query: Query<(Entity, &mut AiBrain, &Perception, &Transform, Option<&Team>)>,
+    target_query: Query<&Team, (With<Health>, Without<Bullet>)>,
+    parent_query: Query<&Parent>,
+    bullet_query: Query<(), With<Bullet>>,
+    spatial_hash: Res<SpatialHash>,
+    rapier_context: Res<RapierContext>,
+    mut noise_events: EventReader<NoiseEvent>,
+    time: Res<Time>,
+) {
+    let noises: Vec<NoiseEvent> = noise_events.read().copied().collect();
+    for (entity, mut brain, perception, transform, team) in ai_query.iter_mut() {
+        // robots aren't parented to anything, so their Transform is already where they are in the
+        // world, and unlike GlobalTransform it's right on the frame they spawn
+        let position = transform.translation;
+        // the front of a robot is +z, the opposite of bevy's forward
+        let facing = transform.back().as_vec3();
+
+        // cheap checks first, rays are only cast for targets inside the view cone
+        let mut in_view: Vec<(Entity, Vec3)> = spatial_hash
+            .within(position, perception.view_distance)
+            .filter(|(other, other_position)| {
+                is_enemy(&target_query, *other, team)
+                    && perception.in_view(facing, *other_position - position)
+            })
+            .collect();
+        in_view.sort_by(|(_, a), (_, b)| a.distance(position).total_cmp(&b.distance(position)));
+        let seen = in_view.into_iter().find(|(other, other_position)| {
+            has_line_of_sight(
+                &rapier_context,
+                position,
+                entity,
+                *other_position,
+                *other,
+                &parent_query,
+                &bullet_query,
+            )
+        });
+        let heard = noises
+            .iter()
+            .filter(|noise| {
+                noise.source != entity && team.is_none_or(|team| noise.team != Some(*team))
+            })
+            // a noise carries as far as it's loud, but no further than the robot can hear
+            .filter(|noise| {
+                let distance = noise.position.distance(position);
+                distance <= noise.radius.min(perception.hearing_radius)
+            })
+            .map(|noise| noise.position)
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+
+        brain.target = seen.map(|(other, _)| other);
+        brain.memory -= time.delta_seconds();
+        if let Some(last_known) = seen.map(|(_, other_position)| other_position).or(heard) {
+            brain.last_known = Some(last_known);
+            brain.memory = perception.memory_time;
+        } else if brain.memory <= 0.0 {
+            brain.last_known = None;
+        }
+    }
+}
+
+// Decides what to do about what the robot has perceived:
+//   low on health and knows about a target -> Retreat
+//   can see a target within attack range   -> Attack
+//   can see a target                       -> Chase
+//   remembers where a target was           -> Investigate, pathing there around any cover
+//   nothing                                -> take turns at Idle and Patrol
+pub fn update_ai_state(
+    mut commands: Commands,
+    mut ai_query: Query<(Entity, &mut AiBrain, &AiTuning, &GlobalTransform, &Health)>,
+    target_query: Query<&GlobalTransform>,
+    time: Res<Time>,
+) {
+    for (entity, mut brain, tuning, transform, health) in ai_query.iter_mut() {
+        brain.time_in_state += time.delta_seconds();
+        let position = transform.translation();
+
+        let target_distance = brain
+            .target
+            .and_then(|target| target_query.get(target).ok())
+            .map(|target_transform| target_transform.translation().distance(position));
+        let knows_of_target = target_distance.is_some() || brain.last_known.is_some();
+        let health_fraction = health.value / brain.max_health.max(f32::EPSILON);
+        let next = match target_distance {
+            _ if knows_of_target && health_fraction < tuning.retreat_health => AiState::Retreat,
+            Some(distance) if distance <= tuning.attack_range => AiState::Attack,
+            Some(_) => AiState::Chase,
+            None if brain.last_known.is_some() => AiState::Investigate,
+            None => match brain.state {
+                AiState::Idle if brain.time_in_state >= tuning.idle_time => AiState::Patrol,
+                AiState::Patrol if brain.time_in_state >= tuning.patrol_time => AiState::Idle,
+                AiState::Idle | AiState::Patrol => brain.state,
+                // lost the target, have a breather before patrolling again
+                AiState::Chase | AiState::Attack | AiState::Retreat | AiState::Investigate => {
+                    AiState::Idle
+                }
+            },
+        };
+
+        // investigating goes the long way round obstacles, anything else drops the path
+        if next != brain.state {
+            if let (AiState::Investigate, Some(last_known)) = (next, brain.last_known) {
+                commands
+                    .entity(entity)
+                    .insert(PathRequest::new(last_known, tuning.max_speed));
+            } else if brain.state == AiState::Investigate {
+                commands
+                    .entity(entity)
+                    .remove::<(PathRequest, PathTask, FollowPath)>();
+            }
+        }
+        brain.set_state(next);
+    }
+}
+
+// turns the AI's state into a steering force in its Acceleration, and turns the robot to face the
+// way it's going. A robot that's following a path leaves the driving to the path.
+#[allow(clippy::type_complexity)]
+pub fn steer_ai(
+    mut ai_query: Query<
+        (
+            Entity,
+            &mut AiBrain,
+            &AiTuning,
+            &mut Transform,
+            &Velocity,
+            &mut Acceleration,
+            Option<&StatusEffects>,
+        ),
+        Without<FollowPath>,
+    >,
+    target_query: Query<(&GlobalTransform, Option<&Velocity>)>,
+    robot_query: Query<(), With<AiBrain>>,
+    spatial_hash: Res<SpatialHash>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (entity, mut brain, tuning, mut transform, velocity, mut acceleration, status_effects) in
+        ai_query.iter_mut()
+    {
+        // a stunned robot can't drive, it just rolls to a stop
+        if status_effects.is_some_and(|status_effects| status_effects.is_stunned()) {
+            acceleration.value = Vec3::ZERO;
+            continue;
+        }
+
+        let position = transform.translation;
+        let velocity = velocity.value;
+        let max_speed = tuning.max_speed;
+        let target = brain
+            .target
+            .and_then(|target| target_query.get(target).ok())
+            .map(|(target_transform, target_velocity)| {
+                (
+                    target_transform.translation(),
+                    target_velocity.map_or(Vec3::ZERO, |velocity| velocity.value),
+                )
+            });
+
+        let force = match (brain.state, target) {
+            (AiState::Chase, Some((target, target_velocity))) => {
+                pursue(position, velocity, target, target_velocity, max_speed)
+            }
+            // gunners hang back at keep_distance, everything else charges straight in to ram
+            (AiState::Attack, Some((target, _))) if tuning.keep_distance > 0.0 => {
+                let away = (position - target).normalize_or_zero();
+                let standoff = target + away * tuning.keep_distance;
+                arrive(
+                    position,
+                    velocity,
+                    standoff,
+                    max_speed,
+                    tuning.slowing_radius,
+                )
+            }
+            (AiState::Attack, Some((target, _))) => seek(position, velocity, target, max_speed),
+            (AiState::Retreat, Some((target, target_velocity))) => {
+                evade(position, velocity, target, target_velocity, max_speed)
+            }
+            // can't see it any more, keep running from where it was
+            (AiState::Retreat, None) => match brain.last_known {
+                Some(last_known) => flee(position, velocity, last_known, max_speed),
+                None => Vec3::ZERO,
+            },
+            // the path has got it here, creep up to the exact spot
+            (AiState::Investigate, _) => arrive(
+                position,
+                velocity,
+                brain.last_known.unwrap_or(position),
+                max_speed,
+                tuning.slowing_radius,
+            ),
+            (AiState::Patrol, _) => {
+                // wander about, but head back home if it's strayed too far
+                if position.distance(brain.home) > tuning.patrol_radius {
+                    arrive(
+                        position,
+                        velocity,
+                        brain.home,
+                        max_speed,
+                        tuning.slowing_radius,
+                    )
+                } else {
+                    let jitter = tuning.wander_jitter * time.delta_seconds();
+                    wander(
+                        velocity,
+                        &mut brain.wander_angle,
+                        jitter,
+                        max_speed,
+                        &mut rng,
+                    )
+                }
+            }
+            // idle, or the target has gone, so stop where it is
+            _ => arrive(
+                position,
+                velocity,
+                position,
+                max_speed,
+                tuning.slowing_radius,
+            ),
+        };
+
+        // steers away from where the other robots were at the start of the frame
+        let neighbours = spatial_hash
+            .within(position, tuning.separation_radius)
+            .filter(|(other, _)| *other != entity && robot_query.contains(*other))
+            .map(|(_, other_position)| other_position);
+        let push = separation(position, neighbours, tuning.separation_radius);
+
+        acceleration.value = (force + push * tuning.max_force * tuning.separation_weight)
+            .clamp_length_max(tuning.max_force);
+
+        // the front of a robot is +z, the opposite of bevy's forward
+        let heading = Vec3::new(velocity.x, 0.0, velocity.z);
+        if heading.length_squared() > 0.01 {
+            transform.look_to(-heading, Vec3::Y);
+        }
+        // once it's where the target was it turns on the spot looking for it
+        let searching = brain.state == AiState::Investigate
+            && brain
+                .last_known
+                .is_some_and(|last_known| last_known.distance(position) < tuning.slowing_radius);
+        if searching {
+            transform.rotate_y(LOOK_AROUND_SPEED * time.delta_seconds());
+        }
+        // keep wandering from the way it's facing once it starts patrolling
+        if brain.state != AiState::Patrol {
+            brain.wander_angle = heading.x.atan2(heading.z);
+        }
+    }
+}
+
+// Boids: every swarm robot steers by its flock (the swarm robots around it) plus a pull towards the
+// nearest robot on the other team. Neighbours come from the spatial hash, so hundreds of them only
+// look at who's close by instead of at each other.
+#[allow(clippy::type_complexity)]
+pub fn steer_swarm(
+    mut swarm_query: Query<
+        (
+            Entity,
+            &SwarmTuning,
+            &mut Transform,
+            &Velocity,
+            &mut Acceleration,
+            Option<&Team>,
+            Option<&StatusEffects>,
+        ),
+        Without<FollowPath>,
+    >,
+    flock_query: Query<&Velocity, With<SwarmTuning>>,
+    target_query: Query<&Team, (With<Health>, Without<Bullet>)>,
+    spatial_hash: Res<SpatialHash>,
+) {
+    for (entity, tuning, mut transform, velocity, mut acceleration, team, status_effects) in
+        swarm_query.iter_mut()
+    {
+        if status_effects.is_some_and(|status_effects| status_effects.is_stunned()) {
+            acceleration.value = Vec3::ZERO;
+            continue;
+        }
+        let position = transform.translation;
+        let velocity = velocity.value;
+        let max_speed = tuning.max_speed;
+
+        let flock: Vec<(Vec3, Vec3)> = spatial_hash
+            .within(position, tuning.neighbour_radius)
+            .filter(|(other, _)| *other != entity)
+            .filter_map(|(other, other_position)| {
+                let other_velocity = flock_query.get(other).ok()?;
+                Some((other_position, other_velocity.value))
+            })
+            .collect();
+        let crowded = flock
+            .iter()
+            .map(|(other_position, _)| *other_position)
+            .filter(|other_position| other_position.distance(position) < tuning.separation_radius);
+
+        let mut force = cohesion(
+            position,
+            velocity,
+            flock.iter().map(|(other_position, _)| *other_position),
+            max_speed,
+        ) * tuning.cohesion_weight
+            + alignment(
+                velocity,
+                flock.iter().map(|(_, other_velocity)| *other_velocity),
+                max_speed,
+            ) * tuning.alignment_weight
+            + separation(position, crowded, tuning.separation_radius)
+                * tuning.max_force
+                * tuning.separation_weight;
+        if let Some((_, target)) = nearest_enemy(
+            &spatial_hash,
+            &target_query,
+            position,
+            team,
+            tuning.sight_range,
+        ) {
+            force += seek(position, velocity, target, max_speed) * tuning.seek_weight;
+        }
+        acceleration.value = force.clamp_length_max(tuning.max_force);
+
+        // the front of a robot is +z, the opposite of bevy's forward
+        let heading = Vec3::new(velocity.x, 0.0, velocity.z);
+        if heading.length_squared() > 0.01 {
+            transform.look_to(-heading, Vec3::Y);
+        }
+    }
+}
+
+// the closest robot on another team within range, and where it is
+fn nearest_enemy(
+    spatial_hash: &SpatialHash,
+    target_query: &Query<&Team, (With<Health>, Without<Bullet>)>,
+    position: Vec3,
+    team: Option<&Team>,
+    range: f32,
+) -> Option<(Entity, Vec3)> {
+    spatial_hash
+        .within(position, range)
+        .filter(|(entity, _)| is_enemy(target_query, *entity, team))
+        .min_by(|(_, a), (_, b)| a.distance(position).total_cmp(&b.distance(position)))
+}
+
+// whether entity is a robot worth going after for something on team
+fn is_enemy(
+    target_query: &Query<&Team, (With<Health>, Without<Bullet>)>,
+    entity: Entity,
+    team: Option<&Team>,
+) -> bool {
+    target_query
+        .get(entity)
+        .is_ok_and(|target_team| team != Some(target_team))
+}
+
+// AI robots shoot at their target whenever a weapon is ready and the target is in range. The
+// target is only set while it can be seen, so nothing shoots through walls. Shots are led so they
+// land where the target is going to be.
+#[allow(clippy::type_complexity)]
+pub fn ai_fire_weapons(
+    mut prefabs: PrefabSpawner,
+    mut noise_events: EventWriter<NoiseEvent>,
+    registry: Res<PrefabRegistry>,
+    mut ai_query: Query<(
+        Entity,
+        &AiBrain,
+        &GlobalTransform,
+        &mut Weapons,
+        Option<&Team>,
+        Option<&StatusEffects>,
+    )>,
+    target_query: Query<(&GlobalTransform, Option<&Velocity>)>,
+) {
+    for (entity, brain, transform, mut weapons, team, status_effects) in ai_query.iter_mut() {
+        if status_effects.is_some_and(|status_effects| status_effects.weapons_disabled()) {
+            continue;
+        }
+        let Some((target_transform, target_velocity)) = brain
+            .target
+            .and_then(|target| target_query.get(target).ok())
+        else {
+            continue;
+        };
+        let position = transform.translation();
+        let target_position = target_transform.translation();
+        let target_velocity = target_velocity.map_or(Vec3::ZERO, |velocity| velocity.value);
+        let distance = position.distance(target_position);
+
+        for weapon in weapons.0.iter_mut() {
+            if weapon.cooldown > 0.0 || distance > weapon.range {
+                continue;
+            }
+            let projectile_speed = registry
+                .get(&weapon.projectile)
+                .map_or(0.0, |projectile| projectile.velocity.length());
+            let aim = lead_target(position, target_position, target_velocity, projectile_speed);
+            fire_weapon(
+                &mut prefabs,
+                &mut noise_events,
+                weapon,
+                entity,
+                position,
+                aim,
+                team.copied(),
+            );
+        }
+    }
+}
+
+// draws each AI robot's view cone, and a line to where it thinks its target is, only with dev
+// tools on
+pub fn draw_perception(
+    mut gizmos: Gizmos,
+    query: Query<(&GlobalTransform, &Perception, &AiBrain)>,
+) {
+    let cone_colour = Color::srgb(1.0, 1.0, 0.3);
+    let memory_colour = Color::srgb(1.0, 0.5, 0.0);
+    for (transform, perception, brain) in query.iter() {
+        let position = transform.translation();
+        let facing = transform.back().as_vec3().with_y(0.0).normalize_or_zero();
+        let half_angle = (perception.view_angle * 0.5).to_radians();
+        for side in [-half_angle, half_angle] {
+            let edge = Quat::from_rotation_y(side) * facing * perception.view_distance;
+            gizmos.line(position, position + edge, cone_colour);
+        }
+        if let Some(last_known) = brain.last_known {
+            gizmos.line(position, last_known, memory_colour);
+        }
+    }
+}
+
+// casts a ray from the looker to the target, the target is visible if the first thing it hits is
+// the target itself or one of its hit zones
+fn has_line_of_sight(
+    rapier_context: &RapierContext,
+    position: Vec3,
+    looker: Entity,
+    target_position: Vec3,
+    target: Entity,
+    parent_query: &Query<&Parent>,
+    bullet_query: &Query<(), With<Bullet>>,
+) -> bool {
+    let offset = target_position - position;
+    let distance = offset.length();
+    if distance <= f32::EPSILON {
+        return true;
+    }
+    // bullets flying past shouldn't block the view, and neither should the looker's own hit zones
+    // that the ray starts inside
+    let can_block = |entity| {
+        !bullet_query.contains(entity)
+            && parent_query
+                .get(entity)
+                .map_or(true, |parent| parent.get() != looker)
+    };
+    let filter = QueryFilter::default()
+        .exclude_collider(looker)
+        .predicate(&can_block);
+    match rapier_context.cast_ray(position, offset / distance, distance, true, filter) {
+        Some((hit, _)) => {
+            hit == target
+                || parent_query
+                    .get(hit)
+                    .is_ok_and(|parent| parent.get() == target)
+        }
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy_rapier3d::prelude::{Collider, NoUserData, RapierPhysicsPlugin};
+
+    use super::*;
+    use crate::{archetypes::default_hit_zones, combat::HitZoneBundle};
+
+    // a unit box robot with the usual hit zones, facing +z like robots do when they drive
+    fn spawn_robot(app: &mut App, position: Vec3) -> Entity {
+        app.world_mut()
+            .spawn((
+                Collider::cuboid(0.5, 0.5, 0.5),
+                TransformBundle::from_transform(Transform::from_translation(position)),
+            ))
+            .with_children(|parent| {
+                for zone in default_hit_zones(Vec3::ONE, true) {
+                    let half_size = zone.half_size;
+                    parent.spawn(HitZoneBundle::new(
+                        zone.kind,
+                        zone.multiplier,
+                        Collider::cuboid(half_size.x, half_size.y, half_size.z),
+                        zone.centre,
+                    ));
+                }
+            })
+            .id()
+    }
+
+    #[test]
+    fn robots_see_past_their_own_hit_zones() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            HierarchyPlugin,
+            AssetPlugin::default(),
+            bevy::scene::ScenePlugin,
+            RapierPhysicsPlugin::<NoUserData>::default(),
+        ))
+        .init_asset::<Mesh>();
+        let looker = spawn_robot(&mut app, Vec3::ZERO);
+        let target = spawn_robot(&mut app, Vec3::new(0.0, 0.0, 5.0));
+        // once for the colliders to be added to rapier and once more for them to be queryable
+        app.update();
+        app.update();
+
+        let visible = app.world_mut().run_system_once(
+            move |rapier_context: Res<RapierContext>,
+                  parent_query: Query<&Parent>,
+                  bullet_query: Query<(), With<Bullet>>| {
+                has_line_of_sight(
+                    &rapier_context,
+                    Vec3::ZERO,
+                    looker,
+                    Vec3::new(0.0, 0.0, 5.0),
+                    target,
+                    &parent_query,
+                    &bullet_query,
+                )
+            },
+        );
+        assert!(visible);
+    }
+}
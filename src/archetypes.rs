@@ -0,0 +1,280 @@
+// Enemy archetypes live in assets/enemies as .enemy.ron files. Everything about an enemy (how it
+// looks, how tough it is, how it moves, what it drops) comes from its file, so adding a new enemy
+// is just adding a new file. Anything can then spawn it by name with PrefabSpawner.
+use crate::{
+    ai::{AiTuning, Perception, SwarmTuning},
+    boss::BossScript,
+    combat::{Armor, HitZoneKind, Team, Weapon},
+    pickups::{Loot, LootDrop},
+    prefabs::{Prefab, PrefabCollider, PrefabHitZone, PrefabModel, PrefabRole},
+    scene_collider::ColliderGen,
+};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use serde::Deserialize;
+
+pub struct ArchetypePlugin;
+
+impl Plugin for ArchetypePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<EnemyArchetype>()
+            .init_asset_loader::<EnemyArchetypeLoader>()
+            .register_type::<Archetype>()
+            .register_type::<AiProfile>();
+    }
+}
+
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct EnemyArchetype {
+    pub name: String,         // what the archetype is spawned by, e.g. "roaming_dummy"
+    pub display_name: String, // the Name the spawned entity gets
+    pub model: EnemyModel,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    pub health: f32,
+    #[serde(default)]
+    pub armor: f32, // fraction of every hit that's ignored, see Armor
+    pub mass: f32,
+    pub speed: f32,
+    pub collision_damage: f32,
+    pub ramming: f32, // ramming damage per unit of impact speed
+    pub ai: AiProfile,
+    #[serde(default)]
+    pub perception: Perception, // what the steering AI can see and hear
+    #[serde(default)]
+    pub weapon: Option<Weapon>,
+    pub team: Team,
+    #[serde(default)]
+    pub loot: Vec<LootDrop>,
+    #[serde(default)]
+    pub boss: Option<BossScript>, // phases and attack patterns, only bosses have these
+    #[serde(default)]
+    pub hit_zones: Vec<EnemyHitZone>, // left out, they're worked out from the size of the model
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Deserialize)]
+pub enum EnemyModel {
+    // a plain box, size is the full width, height and depth and colour is rgb from 0.0 to 1.0
+    Cuboid {
+        size: [f32; 3],
+        colour: [f32; 3],
+    },
+    // an imported model like "test.glb#Scene0", its collider is generated once it has spawned
+    Scene {
+        path: String,
+        #[serde(default)]
+        collider: ColliderGen,
+        #[serde(skip)]
+        handle: Handle<Scene>,
+    },
+}
+
+// A part of the enemy that takes more or less damage than the rest, the same as the block's hit
+// zones. The front of an enemy is +z since that's the way it faces when it moves.
+#[derive(Debug, Deserialize)]
+pub struct EnemyHitZone {
+    pub kind: HitZoneKind,
+    pub multiplier: f32,
+    pub centre: [f32; 3],
+    pub half_size: [f32; 3],
+}
+
+// How an enemy moves about
+#[derive(Component, Debug, Clone, Copy, Deserialize, Reflect)]
+pub enum AiProfile {
+    Stationary,         // never moves, good for target practice
+    Steering(AiTuning), // hunts down the other team with the steering AI, see the ai module
+    Swarm(SwarmTuning), // flocks with others of its kind and swarms the other team
+}
+
+// the archetype an entity was spawned from
+#[derive(Component, Debug, Reflect)]
+pub struct Archetype(pub String);
+
+#[derive(Default)]
+pub struct EnemyArchetypeLoader;
+
+#[derive(Debug)]
+pub enum EnemyArchetypeLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl std::fmt::Display for EnemyArchetypeLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnemyArchetypeLoaderError::Io(error) => write!(f, "could not read archetype: {error}"),
+            EnemyArchetypeLoaderError::Ron(error) => write!(f, "invalid archetype: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for EnemyArchetypeLoaderError {}
+
+impl AssetLoader for EnemyArchetypeLoader {
+    type Asset = EnemyArchetype;
+    type Settings = ();
+    type Error = EnemyArchetypeLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<EnemyArchetype, EnemyArchetypeLoaderError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(EnemyArchetypeLoaderError::Io)?;
+        let mut archetype: EnemyArchetype =
+            ron::de::from_bytes(&bytes).map_err(EnemyArchetypeLoaderError::Ron)?;
+        // loading the model through the load context makes it a dependency of the archetype, so
+        // the loading screen waits for it too
+        if let EnemyModel::Scene { path, handle, .. } = &mut archetype.model {
+            *handle = load_context.load(path.clone());
+        }
+        Ok(archetype)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemy.ron"]
+    }
+}
+
+pub fn find_archetype<'a>(
+    archetypes: &'a Assets<EnemyArchetype>,
+    name: &str,
+) -> Option<&'a EnemyArchetype> {
+    archetypes
+        .iter()
+        .map(|(_, archetype)| archetype)
+        .find(|archetype| archetype.name == name)
+}
+
+impl EnemyArchetype {
+    pub fn prefab(&self) -> Prefab {
+        let (model, collider, size) = match &self.model {
+            EnemyModel::Cuboid { size, colour } => (
+                PrefabModel::Cuboid {
+                    size: Vec3::from(*size),
+                    colour: Color::srgb(colour[0], colour[1], colour[2]),
+                },
+                PrefabCollider::Cuboid {
+                    half_size: Vec3::from(*size) / 2.0,
+                },
+                Vec3::from(*size),
+            ),
+            EnemyModel::Scene { path, collider, .. } => (
+                PrefabModel::Scene {
+                    path: path.clone(),
+                    collider: Some(*collider),
+                },
+                // stands in until the real collider has been generated from the model
+                PrefabCollider::Ball { radius: 0.5 },
+                Vec3::ONE,
+            ),
+        };
+        let hit_zones = if self.hit_zones.is_empty() {
+            default_hit_zones(size, self.weapon.is_some())
+        } else {
+            self.hit_zones
+                .iter()
+                .map(|zone| PrefabHitZone {
+                    kind: zone.kind,
+                    multiplier: zone.multiplier,
+                    centre: Vec3::from(zone.centre),
+                    half_size: Vec3::from(zone.half_size),
+                })
+                .collect()
+        };
+        Prefab {
+            name: self.display_name.clone(),
+            role: PrefabRole::Enemy,
+            model,
+            collider,
+            scale: self.scale,
+            health: self.health,
+            collision_damage: self.collision_damage,
+            mass: self.mass,
+            team: Some(self.team),
+            ramming: Some(self.ramming),
+            status_effects: true,
+            weapons: self.weapon.iter().cloned().collect(),
+            hit_zones,
+            ..default()
+        }
+    }
+
+    // everything an enemy gets on top of its prefab
+    pub fn components(&self) -> impl Bundle {
+        (
+            Archetype(self.name.clone()),
+            match self.ai {
+                // the archetype's speed is the AI's top speed
+                AiProfile::Steering(tuning) => AiProfile::Steering(AiTuning {
+                    max_speed: self.speed,
+                    ..tuning
+                }),
+                AiProfile::Swarm(tuning) => AiProfile::Swarm(SwarmTuning {
+                    max_speed: self.speed,
+                    ..tuning
+                }),
+                AiProfile::Stationary => AiProfile::Stationary,
+            },
+            self.perception,
+            Armor(self.armor),
+            Loot(self.loot.clone()),
+        )
+    }
+}
+
+// Armour on the front, a weak back, wheels down both sides and a weapon on top if it has one, all
+// fitted to a box of the given size like the block's are
+pub(crate) fn default_hit_zones(size: Vec3, armed: bool) -> Vec<PrefabHitZone> {
+    let half = size / 2.0;
+    let zone = |kind, multiplier, centre, half_size| PrefabHitZone {
+        kind,
+        multiplier,
+        centre,
+        half_size,
+    };
+    let front = Vec3::new(0.0, 0.0, half.z * 0.75);
+    let side = Vec3::new(half.x * 0.85, -half.y * 0.5, 0.0);
+    let end = Vec3::new(half.x, half.y, half.z * 0.25);
+    let wheel = Vec3::new(half.x * 0.15, half.y * 0.5, half.z * 0.5);
+    let mut zones = vec![
+        zone(HitZoneKind::FrontArmor, 0.5, front, end),
+        zone(HitZoneKind::Rear, 2.0, -front, end),
+        zone(HitZoneKind::Wheels, 1.0, side, wheel),
+        zone(HitZoneKind::Wheels, 1.0, side.with_x(-side.x), wheel),
+    ];
+    if armed {
+        let top = Vec3::new(0.0, half.y * 0.75, 0.0);
+        zones.push(zone(HitZoneKind::Weapon, 1.0, top, half * 0.25));
+    }
+    zones
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_enemy_file_parses() {
+        let folder = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/enemies");
+        for entry in std::fs::read_dir(folder).unwrap() {
+            let path = entry.unwrap().path();
+            let text = std::fs::read_to_string(&path).unwrap();
+            if let Err(error) = ron::de::from_str::<EnemyArchetype>(&text) {
+                panic!("{} is not a valid enemy archetype: {error}", path.display());
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+// What happens to something that ends up past the edge of the arena
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum OutOfBounds {
+    Clamp,   // put back inside and stop moving outwards, like driving into the wall
+    Bounce,  // put back inside and rebound off the wall
+    Kill,    // dies like it would from damage, so it still counts and drops loot
+    Despawn, // quietly removed
+}
+
+// one straight section of the wall around the arena
+#[derive(Component, Debug)]
+pub struct ArenaWall;
+
+// everything the arena is built out of, torn down before another arena is built
+#[derive(Component, Debug)]
+pub struct ArenaScoped;
+
+// Robots can't drive through it and projectiles stop when they hit it, the arena's walls and
+// obstacles are all solid
+#[derive(Component, Debug)]
+pub struct Solid;
+
+// An obstacle that can be shot to pieces. It has its own Health, only projectiles hurt it and once
+// it runs out the cover breaks up into debris.
+#[derive(Component, Debug)]
+pub struct Cover {
+    pub size: Vec3, // how big the whole thing is, the debris is cut out of this
+    pub colour: Color,
+}
+
+// a broken off piece of cover, it flies off, lands and is cleared away once the timer runs out
+#[derive(Component, Debug)]
+pub struct Debris {
+    pub timer: Timer,
+    pub half_height: f32, // how high its middle sits once it's lying on the floor
+}
@@ -0,0 +1,348 @@
+// Arenas live in assets/arenas as .arena.ron files. A file describes everything about the world a
+// round is played in: the shape of the floor, the obstacles on it, the lights, where the camera can
+// sit, where the player and the enemies appear, any hazards and any prefabs placed as props. Adding
+// a new arena is just adding a new file, pick it from the main menu or start the game with
+// `--arena <name>`. There are also generated arenas, see generator.rs.
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{combat::Team, waves::SpawnZone};
+
+use super::ArenaBounds;
+
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct ArenaDefinition {
+    pub name: String,         // what the arena is picked by, e.g. "default"
+    pub display_name: String, // what the menu shows
+    pub floor: FloorShape,
+    #[serde(default = "default_floor_colour")]
+    pub floor_colour: [f32; 3],
+    #[serde(default)]
+    pub obstacles: Vec<ObstacleDefinition>,
+    pub lights: Vec<LightDefinition>,
+    pub cameras: Vec<CameraPreset>, // the first one is used to start with, C cycles through them
+    pub player_spawns: Vec<[f32; 3]>, // the first is where the player starts, respawns use the first clear one
+    pub spawn_zones: Vec<SpawnZoneDefinition>,
+    #[serde(default)]
+    pub hazards: Vec<HazardDefinition>,
+    #[serde(default)]
+    pub mechanisms: Vec<MechanismDefinition>,
+    #[serde(default)]
+    pub props: Vec<PropDefinition>,
+    #[serde(skip)]
+    pub seed: Option<u64>, // set on generated arenas, the same seed builds the same arena again
+}
+
+fn default_floor_colour() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+// A prefab (or enemy archetype) placed in the arena by name, see prefabs.rs. Unlike obstacles these
+// are spawned fresh at the start of every round, so crates that got pushed around or shot to
+// pieces are back where they started.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PropDefinition {
+    pub prefab: String,     // the prefab's file name without .prefab.ron, e.g. "crate"
+    pub position: [f32; 3], // where the middle of the prefab goes
+    #[serde(default)]
+    pub rotation: f32, // degrees around the vertical
+    #[serde(default)]
+    pub team: Option<Team>,
+}
+
+// the edge of the arena, see ArenaBounds
+#[derive(Debug, Clone, Deserialize)]
+pub enum FloorShape {
+    Circle {
+        radius: f32,
+        #[serde(default)]
+        centre: [f32; 2], // x and z
+    },
+    Polygon {
+        points: Vec<[f32; 2]>, // x and z of every corner in order
+    },
+}
+
+impl FloorShape {
+    pub fn bounds(&self) -> ArenaBounds {
+        match self {
+            FloorShape::Circle { radius, centre } => ArenaBounds::Circle {
+                centre: Vec2::from(*centre),
+                radius: *radius,
+            },
+            FloorShape::Polygon { points } => ArenaBounds::Polygon {
+                points: points.iter().copied().map(Vec2::from).collect(),
+            },
+        }
+    }
+}
+
+// Something solid that never moves. It's an obstacle, so paths go around it. With health it's
+// destructible cover instead, and paths go through once it's been destroyed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObstacleDefinition {
+    pub shape: ObstacleShape,
+    pub position: [f32; 3], // the middle of the bottom of the shape
+    #[serde(default)]
+    pub rotation: f32, // degrees around the vertical
+    #[serde(default = "default_obstacle_colour")]
+    pub colour: [f32; 3],
+    #[serde(default)]
+    pub health: Option<f32>, // set this to make it cover that can be shot to pieces
+}
+
+fn default_obstacle_colour() -> [f32; 3] {
+    [0.6, 0.6, 0.65]
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum ObstacleShape {
+    Cuboid { size: [f32; 3] }, // full width, height and depth
+    Cylinder { radius: f32, height: f32 },
+}
+
+// Anything left out uses bevy's default for that kind of light
+#[derive(Debug, Clone, Deserialize)]
+pub enum LightDefinition {
+    Point {
+        position: [f32; 3],
+        #[serde(default)]
+        intensity: Option<f32>, // lumens
+        #[serde(default)]
+        range: Option<f32>,
+        #[serde(default)]
+        shadows: bool,
+    },
+    // light from far away, like the sun, shining in direction
+    Directional {
+        direction: [f32; 3],
+        #[serde(default)]
+        illuminance: Option<f32>, // lux
+        #[serde(default)]
+        shadows: bool,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CameraPreset {
+    pub name: String,
+    pub position: [f32; 3],
+    pub look_at: [f32; 3],
+}
+
+impl CameraPreset {
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(Vec3::from(self.position))
+            .looking_at(Vec3::from(self.look_at), Vec3::Y)
+    }
+}
+
+// a named rectangle enemies can appear in, waves pick their zones by name
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpawnZoneDefinition {
+    pub name: String,
+    pub centre: [f32; 3],
+    pub half_size: [f32; 2], // half the width (x) and depth (z)
+}
+
+// Things in the arena that hurt whoever gets too close, the robots' own weapons aside. They're
+// all fixed in place, see hazards for how each one works.
+#[derive(Debug, Clone, Deserialize)]
+pub enum HazardDefinition {
+    // a patch of floor that hurts anything on it, like a pool of acid
+    DamageZone {
+        centre: [f32; 3],
+        half_size: [f32; 2], // half the width (x) and depth (z)
+        damage_per_second: f32,
+    },
+    // a hole in the floor, any robot that drives over it falls in and is out
+    Pit {
+        centre: [f32; 3],
+        half_size: [f32; 2],
+    },
+    // a blade spinning up out of the floor that chews up anything touching it
+    Saw {
+        centre: [f32; 3],
+        radius: f32,
+        damage: f32, // dealt every frame something touches it, the same as any CollisionDamage
+        #[serde(default)]
+        rotation: f32, // degrees around the vertical, at 0.0 the blade runs along z
+    },
+    // swings down every interval seconds and hits everything within radius of centre
+    Hammer {
+        centre: [f32; 3],
+        radius: f32,
+        damage: f32,
+        interval: f32,
+        #[serde(default)]
+        stun: f32, // how many seconds whatever it hits is stunned for
+    },
+    // a nozzle in the floor that shoots a column of fire for on_time seconds, then goes quiet for
+    // off_time seconds. Anything in the fire is set alight.
+    FlameJet {
+        centre: [f32; 3],
+        radius: f32,
+        #[serde(default = "default_flame_height")]
+        height: f32,
+        on_time: f32,
+        off_time: f32,
+        damage: f32, // dealt every frame something is in the fire
+        burn_damage_per_second: f32,
+        burn_duration: f32,
+    },
+}
+
+fn default_flame_height() -> f32 {
+    2.0
+}
+
+// Parts of the arena that move by themselves, see mechanisms for how each one works. A path is a
+// list of points the part travels between in order, going back the way it came once it reaches the
+// end unless looped is set, in which case it carries on from the last point back to the first.
+#[derive(Debug, Clone, Deserialize)]
+pub enum MechanismDefinition {
+    // a slab of floor travelling along path, carrying whatever's on it along with it
+    Platform {
+        size: [f32; 2], // width (x) and depth (z)
+        path: Vec<[f32; 3]>,
+        speed: f32,
+        #[serde(default)]
+        looped: bool,
+    },
+    // a round piece of floor that spins, anything on it goes round and turns with it
+    Turntable {
+        centre: [f32; 3],
+        radius: f32,
+        speed: f32, // degrees per second, anticlockwise seen from above
+    },
+    // a strip of floor that moves everything on it along its length
+    Conveyor {
+        centre: [f32; 3],
+        size: [f32; 2], // width and length
+        #[serde(default)]
+        rotation: f32, // degrees around the vertical, at 0.0 it carries things towards +z
+        speed: f32,
+    },
+    // a solid block sliding along path that shoves robots out of its way
+    Pusher {
+        size: [f32; 3],
+        path: Vec<[f32; 3]>, // where the middle of its bottom goes
+        speed: f32,
+        #[serde(default)]
+        looped: bool,
+    },
+}
+
+impl ArenaDefinition {
+    // where the player's block first appears
+    pub fn player_spawn(&self) -> Vec3 {
+        self.player_spawns
+            .first()
+            .copied()
+            .map_or(Vec3::new(0.0, 0.5, 0.0), Vec3::from)
+    }
+
+    pub fn player_spawns(&self) -> Vec<Vec3> {
+        self.player_spawns.iter().copied().map(Vec3::from).collect()
+    }
+
+    pub fn spawn_zone(&self, name: &str) -> Option<SpawnZone> {
+        self.spawn_zones
+            .iter()
+            .find(|zone| zone.name == name)
+            .map(|zone| SpawnZone {
+                centre: Vec3::from(zone.centre),
+                half_size: Vec2::from(zone.half_size),
+            })
+    }
+}
+
+// The arena the game falls back on when the one that was picked can't be found. It's
+// assets/arenas/default.arena.ron built into the game, so it's there even if the file isn't.
+const DEFAULT_ARENA: &str = include_str!("../../assets/arenas/default.arena.ron");
+
+impl Default for ArenaDefinition {
+    fn default() -> Self {
+        ron::de::from_str(DEFAULT_ARENA).expect("default.arena.ron should be a valid arena")
+    }
+}
+
+pub fn find_arena<'a>(
+    arenas: &'a Assets<ArenaDefinition>,
+    name: &str,
+) -> Option<&'a ArenaDefinition> {
+    arenas
+        .iter()
+        .map(|(_, arena)| arena)
+        .find(|arena| arena.name == name)
+}
+
+#[derive(Default)]
+pub struct ArenaLoader;
+
+#[derive(Debug)]
+pub enum ArenaLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl std::fmt::Display for ArenaLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArenaLoaderError::Io(error) => write!(f, "could not read arena: {error}"),
+            ArenaLoaderError::Ron(error) => write!(f, "invalid arena: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ArenaLoaderError {}
+
+impl AssetLoader for ArenaLoader {
+    type Asset = ArenaDefinition;
+    type Settings = ();
+    type Error = ArenaLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<ArenaDefinition, ArenaLoaderError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(ArenaLoaderError::Io)?;
+        ron::de::from_bytes(&bytes).map_err(ArenaLoaderError::Ron)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["arena.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_arena_parses() {
+        let arena = ArenaDefinition::default();
+        assert_eq!(arena.name, "default");
+    }
+
+    #[test]
+    fn every_arena_file_parses() {
+        let folder = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/arenas");
+        for entry in std::fs::read_dir(folder).unwrap() {
+            let path = entry.unwrap().path();
+            let text = std::fs::read_to_string(&path).unwrap();
+            if let Err(error) = ron::de::from_str::<ArenaDefinition>(&text) {
+                panic!("{} is not a valid arena: {error}", path.display());
+            }
+        }
+    }
+}
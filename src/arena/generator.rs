@@ -0,0 +1,399 @@
+// Builds a random arena from a seed. Everything random comes out of one ChaCha8Rng seeded with it,
+// which (unlike StdRng) gives the same numbers on every platform and version of rand, so the same
+// seed (and symmetry setting) always gives exactly the same arena and a good one can be shared just
+// by passing its seed around.
+//
+// The player's spawns and the enemy spawn zones are laid out first. Then obstacles are tried one at
+// a time, and an obstacle is only kept if every open bit of floor can still be driven to from the
+// middle, checked with a flood fill over a navigation grid. That way an arena is never split in two.
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::navigation::{reachable_cells, NavGrid, NAV_AGENT_RADIUS};
+
+use super::*;
+
+// the middle is kept clear for the player and the round's first dummies
+const CLEAR_RADIUS: f32 = 7.0;
+const OBSTACLE_ATTEMPTS: u32 = 80;
+const HAZARD_ATTEMPTS: u32 = 40;
+// how far the spawn zones sit in from the edge of the arena
+const SPAWN_ZONE_INSET: f32 = 4.0;
+const COVER_CHANCE: f64 = 0.3;
+const COVER_HEALTH: f32 = 150.0;
+
+// Symmetric arenas are the same either side of the middle (every obstacle, hazard and spawn has a
+// twin on the opposite side), so neither end has an advantage.
+pub fn generate_arena(seed: u64, symmetric: bool) -> ArenaDefinition {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let floor = random_floor(&mut rng, symmetric);
+    let bounds = floor.bounds();
+    let centre = bounds.centre();
+    let inner_radius = bounds.inner_radius();
+    let extent = bounds.half_extent();
+    let mirror = |point: Vec2| centre * 2.0 - point;
+
+    // the player starts in the middle and respawns somewhere near it
+    let mut player_spawns = vec![centre];
+    while player_spawns.len() < 4 {
+        let angle = rng.gen_range(0.0..2.0 * PI);
+        let point = centre + Vec2::from_angle(angle) * rng.gen_range(2.0..CLEAR_RADIUS - 1.0);
+        player_spawns.push(point);
+        if symmetric {
+            player_spawns.push(mirror(point));
+        }
+    }
+
+    // enemies come in from both ends
+    let zone_distance = (inner_radius - SPAWN_ZONE_INSET).max(CLEAR_RADIUS + 1.0);
+    let zone_half_size = Vec2::new((inner_radius * 0.5).min(8.0), 1.5);
+    let zone_shift = if symmetric {
+        0.0
+    } else {
+        rng.gen_range(-1.0..=1.0) * inner_radius * 0.2
+    };
+    let north = centre + Vec2::new(zone_shift, zone_distance);
+    let south = if symmetric {
+        mirror(north)
+    } else {
+        centre + Vec2::new(-zone_shift, -zone_distance)
+    };
+    let zones = [("north", north), ("south", south)];
+    let in_zone = |point: Vec2, margin: f32| {
+        zones.iter().any(|(_, zone)| {
+            let offset = (point - *zone).abs();
+            offset.x <= zone_half_size.x + margin && offset.y <= zone_half_size.y + margin
+        })
+    };
+
+    // everything outside the arena is blocked to start with, obstacles get added to this as they're
+    // accepted
+    let (mut grid, mut blocked) = floor_grid(&bounds);
+
+    let target_obstacles = rng.gen_range(6..=14);
+    let mut obstacles: Vec<ObstacleDefinition> = Vec::new();
+    for _ in 0..OBSTACLE_ATTEMPTS {
+        if obstacles.len() >= target_obstacles {
+            break;
+        }
+        let obstacle = random_obstacle(&mut rng, centre, extent);
+        let mut candidates = vec![obstacle.clone()];
+        if symmetric {
+            let position = mirror(Vec2::new(obstacle.position[0], obstacle.position[2]));
+            // a box turned half way round is the same box, so the twin keeps its rotation
+            candidates.push(ObstacleDefinition {
+                position: [position.x, 0.0, position.y],
+                ..obstacle
+            });
+        }
+
+        // it has to be inside the arena and out of the way of where things spawn
+        let reach = footprint_radius(&candidates[0].shape);
+        let out_of_the_way = candidates.iter().all(|candidate| {
+            let position = Vec2::new(candidate.position[0], candidate.position[2]);
+            bounds.contains(Vec3::new(position.x, 0.0, position.y), reach)
+                && position.distance(centre) > CLEAR_RADIUS + reach
+                && !in_zone(position, reach + 1.0)
+        });
+        if !out_of_the_way {
+            continue;
+        }
+
+        let mut with_candidates = blocked.clone();
+        for candidate in candidates.iter() {
+            block_obstacle(&grid, &mut with_candidates, candidate);
+        }
+        grid.set_blocked(with_candidates.clone());
+        if is_connected(&grid, centre) {
+            blocked = with_candidates;
+            obstacles.extend(candidates);
+        }
+    }
+    grid.set_blocked(blocked.clone());
+
+    // hazards go on open floor, away from spawns
+    let target_hazards = rng.gen_range(1..=3);
+    let mut hazards = Vec::new();
+    for _ in 0..HAZARD_ATTEMPTS {
+        if hazards.len() >= target_hazards * if symmetric { 2 } else { 1 } {
+            break;
+        }
+        let half_size = Vec2::new(rng.gen_range(1.5..3.0), rng.gen_range(1.5..3.0));
+        let damage_per_second = rng.gen_range(10.0..25.0_f32).round();
+        let angle = rng.gen_range(0.0..2.0 * PI);
+        let position = centre + Vec2::from_angle(angle) * rng.gen_range(0.0..inner_radius);
+        let mut positions = vec![position];
+        if symmetric {
+            positions.push(mirror(position));
+        }
+        let reach = half_size.length();
+        let fits = positions.iter().all(|position| {
+            let point = Vec3::new(position.x, 0.0, position.y);
+            bounds.contains(point, reach)
+                && position.distance(centre) > CLEAR_RADIUS + reach
+                && !in_zone(*position, reach)
+                && grid.is_walkable(grid.cell_at(point))
+        });
+        if !fits {
+            continue;
+        }
+        hazards.extend(
+            positions
+                .into_iter()
+                .map(|position| HazardDefinition::DamageZone {
+                    centre: [position.x, 0.0, position.y],
+                    half_size: half_size.into(),
+                    damage_per_second,
+                }),
+        );
+    }
+
+    let shade = rng.gen_range(0.6..0.9);
+    let sun = Vec3::new(rng.gen_range(-0.5..0.5), -1.0, rng.gen_range(-0.5..0.5));
+    let lights = vec![
+        LightDefinition::Directional {
+            direction: sun.into(),
+            illuminance: Some(4000.0),
+            shadows: true,
+        },
+        LightDefinition::Point {
+            position: [centre.x, 8.0, centre.y],
+            intensity: None,
+            range: Some(extent * 2.0),
+            shadows: false,
+        },
+    ];
+    let cameras = vec![
+        CameraPreset {
+            name: "Corner".to_string(),
+            position: [
+                centre.x - extent * 0.45,
+                extent * 0.35,
+                centre.y + extent * 0.45,
+            ],
+            look_at: [centre.x, 0.0, centre.y],
+        },
+        CameraPreset {
+            name: "Overhead".to_string(),
+            position: [centre.x, extent * 1.6, centre.y + 0.1],
+            look_at: [centre.x, 0.0, centre.y],
+        },
+    ];
+
+    ArenaDefinition {
+        name: "generated".to_string(),
+        display_name: if symmetric {
+            format!("Generated {seed} (symmetric)")
+        } else {
+            format!("Generated {seed}")
+        },
+        floor,
+        floor_colour: [shade, shade, shade * 0.95],
+        obstacles,
+        lights,
+        cameras,
+        player_spawns: player_spawns
+            .into_iter()
+            .map(|point| [point.x, 0.5, point.y])
+            .collect(),
+        spawn_zones: zones
+            .into_iter()
+            .map(|(name, zone)| SpawnZoneDefinition {
+                name: name.to_string(),
+                centre: [zone.x, 0.5, zone.y],
+                half_size: zone_half_size.into(),
+            })
+            .collect(),
+        hazards,
+        mechanisms: Vec::new(),
+        props: Vec::new(),
+        seed: Some(seed),
+    }
+}
+
+// A circle, a rectangle or a regular polygon. Symmetric arenas only get shapes that look the same
+// turned half way round, so no odd numbers of sides.
+fn random_floor(rng: &mut ChaCha8Rng, symmetric: bool) -> FloorShape {
+    match rng.gen_range(0..3) {
+        0 => FloorShape::Circle {
+            radius: rng.gen_range(18.0..28.0),
+            centre: [0.0, 0.0],
+        },
+        1 => {
+            let half_width = rng.gen_range(16.0..24.0);
+            let half_depth = rng.gen_range(12.0..18.0);
+            FloorShape::Polygon {
+                points: vec![
+                    [-half_width, -half_depth],
+                    [half_width, -half_depth],
+                    [half_width, half_depth],
+                    [-half_width, half_depth],
+                ],
+            }
+        }
+        _ => {
+            let sides = if symmetric {
+                [6, 8][rng.gen_range(0..2)]
+            } else {
+                rng.gen_range(5..=8)
+            };
+            let radius = rng.gen_range(18.0..26.0);
+            let turn = rng.gen_range(0.0..2.0 * PI);
+            FloorShape::Polygon {
+                points: (0..sides)
+                    .map(|side| {
+                        let corner =
+                            Vec2::from_angle(turn + 2.0 * PI * side as f32 / sides as f32) * radius;
+                        [corner.x, corner.y]
+                    })
+                    .collect(),
+            }
+        }
+    }
+}
+
+fn random_obstacle(rng: &mut ChaCha8Rng, centre: Vec2, extent: f32) -> ObstacleDefinition {
+    let position = centre
+        + Vec2::new(
+            rng.gen_range(-extent..extent),
+            rng.gen_range(-extent..extent),
+        );
+    let shape = if rng.gen_bool(0.7) {
+        ObstacleShape::Cuboid {
+            size: [
+                rng.gen_range(1.0..6.0),
+                rng.gen_range(1.0..3.0),
+                rng.gen_range(1.0..3.0),
+            ],
+        }
+    } else {
+        ObstacleShape::Cylinder {
+            radius: rng.gen_range(0.5..1.5),
+            height: rng.gen_range(1.5..4.0),
+        }
+    };
+    let shade = rng.gen_range(0.4..0.7);
+    let rotation = rng.gen_range(0.0..180.0_f32).round();
+    // some of it is cover that can be shot away, it's brown so it stands out
+    if rng.gen_bool(COVER_CHANCE) {
+        return ObstacleDefinition {
+            shape,
+            position: [position.x, 0.0, position.y],
+            rotation,
+            colour: [shade + 0.2, shade, shade * 0.5],
+            health: Some(COVER_HEALTH),
+        };
+    }
+    ObstacleDefinition {
+        shape,
+        position: [position.x, 0.0, position.y],
+        rotation,
+        colour: [shade, shade, shade + 0.05],
+        health: None,
+    }
+}
+
+// a navigation grid over the whole arena with everything off the floor blocked, and the blocked
+// cells to add obstacles to
+fn floor_grid(bounds: &ArenaBounds) -> (NavGrid, Vec<bool>) {
+    let mut grid = NavGrid::new(bounds.centre(), bounds.half_extent() + 1.0);
+    let mut blocked = Vec::with_capacity((grid.columns * grid.rows) as usize);
+    for row in 0..grid.rows {
+        for column in 0..grid.columns {
+            let point = grid.cell_centre(IVec2::new(column, row), 0.0);
+            blocked.push(!bounds.contains(point, NAV_AGENT_RADIUS));
+        }
+    }
+    grid.set_blocked(blocked.clone());
+    (grid, blocked)
+}
+
+// how far the obstacle reaches from its middle across the floor, whichever way it's turned
+fn footprint_radius(shape: &ObstacleShape) -> f32 {
+    match *shape {
+        ObstacleShape::Cuboid { size } => Vec2::new(size[0], size[2]).length() * 0.5,
+        ObstacleShape::Cylinder { radius, .. } => radius,
+    }
+}
+
+// Marks every cell the obstacle covers (grown by the agent radius, the same as the navigation grid
+// does) as blocked
+fn block_obstacle(grid: &NavGrid, blocked: &mut [bool], obstacle: &ObstacleDefinition) {
+    let position = Vec2::new(obstacle.position[0], obstacle.position[2]);
+    let reach = footprint_radius(&obstacle.shape) + NAV_AGENT_RADIUS + grid.cell_size;
+    let min = grid.cell_at(Vec3::new(position.x - reach, 0.0, position.y - reach));
+    let max = grid.cell_at(Vec3::new(position.x + reach, 0.0, position.y + reach));
+    for row in min.y..=max.y {
+        for column in min.x..=max.x {
+            let cell = IVec2::new(column, row);
+            if !grid.in_bounds(cell) {
+                continue;
+            }
+            let point = grid.cell_centre(cell, 0.0);
+            let offset = Vec2::new(point.x, point.z) - position;
+            let clearance = NAV_AGENT_RADIUS + grid.cell_size * 0.5;
+            let covered = match obstacle.shape {
+                // turned back into the box's own space, where it's lined up with the axes
+                ObstacleShape::Cuboid { size } => {
+                    let local = Vec2::from_angle(obstacle.rotation.to_radians()).rotate(offset);
+                    local.x.abs() <= size[0] * 0.5 + clearance
+                        && local.y.abs() <= size[2] * 0.5 + clearance
+                }
+                ObstacleShape::Cylinder { radius, .. } => offset.length() <= radius + clearance,
+            };
+            if covered {
+                blocked[(row * grid.columns + column) as usize] = true;
+            }
+        }
+    }
+}
+
+// whether every open cell can be reached from the middle
+fn is_connected(grid: &NavGrid, centre: Vec2) -> bool {
+    let reachable = reachable_cells(grid, Vec3::new(centre.x, 0.0, centre.y));
+    grid.blocked
+        .iter()
+        .zip(reachable.iter())
+        .all(|(blocked, reached)| *blocked || *reached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: [u64; 5] = [0, 1, 42, 9_007_199_254_740_993, u64::MAX];
+
+    #[test]
+    fn same_seed_builds_the_same_arena() {
+        for seed in SEEDS {
+            for symmetric in [false, true] {
+                let first = format!("{:?}", generate_arena(seed, symmetric));
+                let second = format!("{:?}", generate_arena(seed, symmetric));
+                assert_eq!(first, second, "seed {seed} changed between runs");
+            }
+        }
+    }
+
+    #[test]
+    fn arenas_are_never_split_in_two() {
+        for seed in SEEDS {
+            for symmetric in [false, true] {
+                let arena = generate_arena(seed, symmetric);
+                let bounds = arena.floor.bounds();
+                let (mut grid, mut blocked) = floor_grid(&bounds);
+                for obstacle in arena.obstacles.iter() {
+                    block_obstacle(&grid, &mut blocked, obstacle);
+                }
+                grid.set_blocked(blocked);
+                assert!(
+                    is_connected(&grid, bounds.centre()),
+                    "seed {seed} is split in two"
+                );
+            }
+        }
+    }
+}
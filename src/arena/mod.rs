@@ -0,0 +1,12 @@
+mod components;
+mod definition;
+mod generator;
+mod plugins;
+mod resources;
+mod systems;
+
+pub use components::*;
+pub use definition::*;
+pub use generator::*;
+pub use plugins::*;
+pub use resources::*;
@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+
+use crate::{schedule::InGameSet, states::GameState};
+
+use super::systems::*;
+use super::{
+    ArenaBounds, ArenaDefinition, ArenaLoader, CameraPresetIndex, CurrentArena, OutOfBounds,
+    SelectedArena,
+};
+
+// Building the arena when loading finishes and the game starts. Anything else that runs then and needs to know where
+// things go in the arena (like where the player starts) should go after this.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BuildArena;
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ArenaBounds>()
+            .register_type::<OutOfBounds>()
+            .init_asset::<ArenaDefinition>()
+            .init_asset_loader::<ArenaLoader>()
+            .init_resource::<ArenaBounds>()
+            .init_resource::<SelectedArena>()
+            .init_resource::<CurrentArena>()
+            .init_resource::<CameraPresetIndex>()
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Loading,
+                    entered: GameState::InGame,
+                },
+                build_arena.in_set(BuildArena),
+            )
+            .add_systems(OnEnter(GameState::MainMenu), teardown_arena)
+            .add_systems(Update, cycle_camera_presets.in_set(InGameSet::UserInput))
+            .add_systems(Update, break_cover.in_set(InGameSet::DespawnEntities))
+            // after everything has moved, so nothing is drawn inside a wall or an obstacle
+            .add_systems(
+                Update,
+                (
+                    (push_out_of_solids, enforce_arena_bounds).chain(),
+                    settle_debris,
+                )
+                    .in_set(InGameSet::CollisionDetection),
+            );
+    }
+}
@@ -0,0 +1,278 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+};
+
+use super::ArenaDefinition;
+
+// the arena everything used to be despawned outside of
+pub const ARENA_RADIUS: f32 = 25.0;
+// a round arena's wall is made of this many straight sections
+const CIRCLE_WALL_SECTIONS: usize = 48;
+
+// The shape of the floor robots can drive on, everything else (the ground, the walls, where
+// things spawn and what happens to anything that leaves) is worked out from this. Only x and z
+// matter, the arena is flat.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub enum ArenaBounds {
+    Circle { centre: Vec2, radius: f32 },
+    // the corners in order, either way round. It should be convex or at least not fold back on
+    // itself as seen from the middle, or the floor won't cover it properly
+    Polygon { points: Vec<Vec2> },
+}
+
+impl Default for ArenaBounds {
+    fn default() -> Self {
+        ArenaBounds::Circle {
+            centre: Vec2::ZERO,
+            radius: ARENA_RADIUS,
+        }
+    }
+}
+
+fn flat(point: Vec3) -> Vec2 {
+    Vec2::new(point.x, point.z)
+}
+
+// the closest point on the segment from start to end
+fn closest_on_segment(point: Vec2, start: Vec2, end: Vec2) -> Vec2 {
+    let edge = end - start;
+    let along = (point - start).dot(edge) / edge.length_squared().max(f32::EPSILON);
+    start + edge * along.clamp(0.0, 1.0)
+}
+
+impl ArenaBounds {
+    pub fn centre(&self) -> Vec2 {
+        match self {
+            ArenaBounds::Circle { centre, .. } => *centre,
+            ArenaBounds::Polygon { points } => {
+                points.iter().copied().sum::<Vec2>() / points.len().max(1) as f32
+            }
+        }
+    }
+
+    // The corners of the wall, in order. A circle gets a polygon just big enough to fit around it,
+    // so the inside of the wall touches the edge of the circle.
+    pub fn corners(&self) -> Vec<Vec2> {
+        match self {
+            ArenaBounds::Circle { centre, radius } => {
+                let corner_radius = radius / (PI / CIRCLE_WALL_SECTIONS as f32).cos();
+                (0..CIRCLE_WALL_SECTIONS)
+                    .map(|section| {
+                        let angle = 2.0 * PI * section as f32 / CIRCLE_WALL_SECTIONS as f32;
+                        *centre + Vec2::from_angle(angle) * corner_radius
+                    })
+                    .collect()
+            }
+            ArenaBounds::Polygon { points } => points.clone(),
+        }
+    }
+
+    // each side of the wall as (start, end, normal pointing into the arena)
+    pub fn edges(&self) -> Vec<(Vec2, Vec2, Vec2)> {
+        let corners = self.corners();
+        // going round anticlockwise the inside is on the left of every edge, clockwise it's on
+        // the right, the sign of the area says which way round the corners go
+        let twice_area: f32 = corners
+            .iter()
+            .zip(corners.iter().cycle().skip(1))
+            .map(|(start, end)| start.perp_dot(*end))
+            .sum();
+        let side = twice_area.signum();
+        corners
+            .iter()
+            .zip(corners.iter().cycle().skip(1))
+            .map(|(start, end)| {
+                (
+                    *start,
+                    *end,
+                    (*end - *start).perp().normalize_or_zero() * side,
+                )
+            })
+            .collect()
+    }
+
+    fn contains_2d(&self, point: Vec2) -> bool {
+        match self {
+            ArenaBounds::Circle { centre, radius } => point.distance(*centre) <= *radius,
+            // count how many edges a line going off in +x crosses, odd means inside
+            ArenaBounds::Polygon { points } => {
+                let mut inside = false;
+                for (start, end) in points.iter().zip(points.iter().cycle().skip(1)) {
+                    if (start.y > point.y) != (end.y > point.y) {
+                        let crossing =
+                            start.x + (point.y - start.y) / (end.y - start.y) * (end.x - start.x);
+                        if point.x < crossing {
+                            inside = !inside;
+                        }
+                    }
+                }
+                inside
+            }
+        }
+    }
+
+    // the closest point on the edge of the arena and the direction back into the arena from there
+    fn nearest_edge(&self, point: Vec2) -> (Vec2, Vec2) {
+        match self {
+            ArenaBounds::Circle { centre, radius } => {
+                let outwards = (point - *centre).normalize_or(Vec2::X);
+                (*centre + outwards * *radius, -outwards)
+            }
+            ArenaBounds::Polygon { .. } => self
+                .edges()
+                .into_iter()
+                .map(|(start, end, normal)| (closest_on_segment(point, start, end), normal))
+                .min_by(|(first, _), (second, _)| {
+                    first
+                        .distance_squared(point)
+                        .total_cmp(&second.distance_squared(point))
+                })
+                .unwrap_or((point, Vec2::ZERO)),
+        }
+    }
+
+    // how far point is from the edge, negative when it's inside
+    pub fn distance_outside(&self, point: Vec3) -> f32 {
+        let point = flat(point);
+        let (edge, _) = self.nearest_edge(point);
+        let distance = point.distance(edge);
+        if self.contains_2d(point) {
+            -distance
+        } else {
+            distance
+        }
+    }
+
+    // whether point is inside with at least margin to spare
+    pub fn contains(&self, point: Vec3, margin: f32) -> bool {
+        self.distance_outside(point) <= -margin
+    }
+
+    // If point is closer than margin to the edge (or past it), where it should be instead and the
+    // direction it was pushed in. None if it's fine where it is.
+    pub fn push_inside(&self, point: Vec3, margin: f32) -> Option<(Vec3, Vec3)> {
+        let distance = self.distance_outside(point);
+        if distance <= -margin {
+            return None;
+        }
+        let (_, normal) = self.nearest_edge(flat(point));
+        let push = Vec3::new(normal.x, 0.0, normal.y);
+        Some((point + push * (distance + margin), push))
+    }
+
+    // point, moved inside if it wasn't already
+    pub fn clamp(&self, point: Vec3, margin: f32) -> Vec3 {
+        self.push_inside(point, margin)
+            .map_or(point, |(inside, _)| inside)
+    }
+
+    // the radius of the biggest circle around the centre that fits inside the arena
+    pub fn inner_radius(&self) -> f32 {
+        match self {
+            ArenaBounds::Circle { radius, .. } => *radius,
+            ArenaBounds::Polygon { .. } => {
+                let centre = self.centre();
+                self.edges()
+                    .into_iter()
+                    .map(|(start, end, _)| closest_on_segment(centre, start, end).distance(centre))
+                    .fold(f32::INFINITY, f32::min)
+            }
+        }
+    }
+
+    // how far the corners reach from the centre along x or z, a square this big covers the arena
+    pub fn half_extent(&self) -> f32 {
+        let centre = self.centre();
+        self.corners()
+            .into_iter()
+            .map(|corner| (corner - centre).abs().max_element())
+            .fold(0.0, f32::max)
+    }
+
+    // the floor, a flat fan of triangles from the centre out to the wall's corners
+    pub fn floor_mesh(&self) -> Mesh {
+        let centre = self.centre();
+        let corners = self.corners();
+        let mut positions = vec![[centre.x, 0.0, centre.y]];
+        positions.extend(corners.iter().map(|corner| [corner.x, 0.0, corner.y]));
+        let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+        let uvs: Vec<[f32; 2]> = positions.iter().map(|[x, _, z]| [*x, *z]).collect();
+
+        let count = corners.len() as u32;
+        let mut indices = Vec::with_capacity(corners.len() * 3);
+        for corner in 0..count {
+            let (first, second) = (corner + 1, (corner + 1) % count + 1);
+            // the triangle has to wind anticlockwise seen from above or it faces the ground
+            let (start, end) = (corners[first as usize - 1], corners[second as usize - 1]);
+            if (start - centre).perp_dot(end - centre) < 0.0 {
+                indices.extend([0, first, second]);
+            } else {
+                indices.extend([0, second, first]);
+            }
+        }
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices))
+    }
+}
+
+// The arena to build when the game starts, picked on the main menu or on the command line with
+// `--arena <name>` for one from a file, or `--seed <number>` (and `--symmetric` to mirror it) for a
+// generated one. `--arena generated` makes a generated one with a random seed.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub enum SelectedArena {
+    File(String), // the arena's name
+    Generated { seed: u64, symmetric: bool },
+}
+
+impl Default for SelectedArena {
+    fn default() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let value_after = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|index| args.get(index + 1))
+        };
+        let symmetric = args.iter().any(|arg| arg == "--symmetric");
+
+        if let Some(seed) = value_after("--seed") {
+            match seed.parse() {
+                Ok(seed) => return SelectedArena::Generated { seed, symmetric },
+                Err(_) => warn!("--seed {seed} isn't a number, ignoring it"),
+            }
+        }
+        match value_after("--arena") {
+            Some(name) if name != "generated" => SelectedArena::File(name.clone()),
+            Some(_) => SelectedArena::Generated {
+                seed: rand::random(),
+                symmetric,
+            },
+            None if symmetric => SelectedArena::Generated {
+                seed: rand::random(),
+                symmetric,
+            },
+            None => SelectedArena::File("default".to_string()),
+        }
+    }
+}
+
+// the arena that's been built, anything that needs to know where things go in it reads this
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CurrentArena(pub ArenaDefinition);
+
+// which of the arena's camera presets the camera is at
+#[derive(Resource, Debug, Default)]
+pub struct CameraPresetIndex(pub usize);
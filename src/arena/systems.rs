@@ -0,0 +1,436 @@
+use bevy::prelude::*;
+use bevy_rapier3d::{
+    na::{Isometry3, Translation3, UnitQuaternion},
+    parry::query::contact,
+    prelude::Collider,
+};
+use rand::Rng;
+
+use crate::{
+    combat::Health,
+    entities::Bullet,
+    hazards::spawn_hazard,
+    mechanisms::spawn_mechanism,
+    movement::{Acceleration, Velocity},
+    navigation::{NavGrid, Obstacle},
+    spatial::SpatialHash,
+};
+
+use super::*;
+
+const WALL_HEIGHT: f32 = 1.5;
+const WALL_THICKNESS: f32 = 0.5;
+// The spatial hash is built before anything moves this frame, so look a little further in than
+// the edge to catch things that have moved out since then. It also has to cover how far a robot's
+// body sticks out from its middle.
+const HASH_SLACK: f32 = 3.0;
+// things pushed out of something solid end up this far from it, so they aren't still touching
+const SOLID_SKIN: f32 = 0.01;
+// cover breaks into this many pieces along each side
+const DEBRIS_SPLITS: i32 = 2;
+const DEBRIS_SPEED: f32 = 4.0;
+const DEBRIS_LIFETIME: f32 = 3.0;
+// debris shrinks away over the last part of its life
+const DEBRIS_SHRINK_TIME: f32 = 0.5;
+const DEBRIS_FRICTION: f32 = 4.0;
+const GRAVITY: f32 = 9.81;
+
+// back at the main menu there's no arena until the next game starts
+pub fn teardown_arena(mut commands: Commands, query: Query<Entity, With<ArenaScoped>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Tears down whatever arena was there before and builds the one that was picked (generating it if
+// it's a generated one), falling back on the default one if it can't be found. Walls and
+// obstacles are obstacles, so the navigation grid rebuilds itself around them.
+#[allow(clippy::too_many_arguments)]
+pub fn build_arena(
+    mut commands: Commands,
+    selected: Res<SelectedArena>,
+    arenas: Res<Assets<ArenaDefinition>>,
+    mut current: ResMut<CurrentArena>,
+    mut bounds: ResMut<ArenaBounds>,
+    mut grid: ResMut<NavGrid>,
+    mut camera_preset: ResMut<CameraPresetIndex>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+    old_query: Query<Entity, With<ArenaScoped>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for entity in old_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let arena = match &*selected {
+        SelectedArena::File(name) => find_arena(&arenas, name).cloned().unwrap_or_else(|| {
+            warn!("no arena called {name}, using the default one");
+            ArenaDefinition::default()
+        }),
+        SelectedArena::Generated { seed, symmetric } => generate_arena(*seed, *symmetric),
+    };
+    info!("building arena {}", arena.display_name);
+
+    *bounds = arena.floor.bounds();
+    // a fresh grid the size of the new arena, the version carries on so old paths count as stale
+    let version = grid.version + 1;
+    *grid = NavGrid::new(bounds.centre(), bounds.half_extent() + WALL_THICKNESS);
+    grid.version = version;
+
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(bounds.floor_mesh()),
+            material: materials.add(Color::srgb_from_array(arena.floor_colour)),
+            ..default()
+        },
+        ArenaScoped,
+        Name::new("Floor"),
+    ));
+    spawn_walls(&mut commands, &bounds, &mut meshes, &mut materials);
+
+    for obstacle in arena.obstacles.iter() {
+        let (mesh, collider, size) = match obstacle.shape {
+            ObstacleShape::Cuboid { size } => (
+                meshes.add(Cuboid::new(size[0], size[1], size[2])),
+                Collider::cuboid(size[0] * 0.5, size[1] * 0.5, size[2] * 0.5),
+                Vec3::from(size),
+            ),
+            ObstacleShape::Cylinder { radius, height } => (
+                meshes.add(Cylinder::new(radius, height)),
+                Collider::cylinder(height * 0.5, radius),
+                Vec3::new(radius * 2.0, height, radius * 2.0),
+            ),
+        };
+        let transform =
+            Transform::from_translation(Vec3::from(obstacle.position) + Vec3::Y * size.y * 0.5)
+                .with_rotation(Quat::from_rotation_y(obstacle.rotation.to_radians()));
+        let colour = Color::srgb_from_array(obstacle.colour);
+        let mut entity = commands.spawn((
+            PbrBundle {
+                mesh,
+                material: materials.add(colour),
+                transform,
+                ..default()
+            },
+            collider,
+            Obstacle,
+            Solid,
+            ArenaScoped,
+            Name::new("Obstacle"),
+        ));
+        if let Some(health) = obstacle.health {
+            entity.insert((
+                Health::new(health),
+                Cover { size, colour },
+                Name::new("Cover"),
+            ));
+        }
+    }
+
+    for light in arena.lights.iter() {
+        match *light {
+            LightDefinition::Point {
+                position,
+                intensity,
+                range,
+                shadows,
+            } => {
+                let default_light = PointLight::default();
+                commands.spawn((
+                    PointLightBundle {
+                        point_light: PointLight {
+                            intensity: intensity.unwrap_or(default_light.intensity),
+                            range: range.unwrap_or(default_light.range),
+                            shadows_enabled: shadows,
+                            ..default_light
+                        },
+                        transform: Transform::from_translation(Vec3::from(position)),
+                        ..default()
+                    },
+                    ArenaScoped,
+                ));
+            }
+            LightDefinition::Directional {
+                direction,
+                illuminance,
+                shadows,
+            } => {
+                let default_light = DirectionalLight::default();
+                commands.spawn((
+                    DirectionalLightBundle {
+                        directional_light: DirectionalLight {
+                            illuminance: illuminance.unwrap_or(default_light.illuminance),
+                            shadows_enabled: shadows,
+                            ..default_light
+                        },
+                        transform: Transform::default().looking_to(Vec3::from(direction), Vec3::Y),
+                        ..default()
+                    },
+                    ArenaScoped,
+                ));
+            }
+        }
+    }
+
+    for hazard in arena.hazards.iter() {
+        spawn_hazard(&mut commands, hazard, &mut meshes, &mut materials).insert(ArenaScoped);
+    }
+    for mechanism in arena.mechanisms.iter() {
+        spawn_mechanism(&mut commands, mechanism, &mut meshes, &mut materials).insert(ArenaScoped);
+    }
+
+    camera_preset.0 = 0;
+    if let Some(preset) = arena.cameras.first() {
+        for mut transform in camera_query.iter_mut() {
+            *transform = preset.transform();
+        }
+    }
+    current.0 = arena;
+}
+
+// A straight section of wall along every edge of the arena, standing just outside it. They're
+// obstacles, so paths go around them, and nothing ever moves them.
+fn spawn_walls(
+    commands: &mut Commands,
+    bounds: &ArenaBounds,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    let material = materials.add(Color::srgb(0.4, 0.4, 0.45));
+    for (start, end, normal) in bounds.edges() {
+        // each section is a little longer than its edge so the corners don't leave gaps
+        let length = start.distance(end) + WALL_THICKNESS;
+        let middle = (start + end) * 0.5 - normal * WALL_THICKNESS * 0.5;
+        let direction = (end - start).normalize_or(Vec2::X);
+        let transform = Transform::from_xyz(middle.x, WALL_HEIGHT * 0.5, middle.y).with_rotation(
+            Quat::from_rotation_arc(Vec3::X, Vec3::new(direction.x, 0.0, direction.y)),
+        );
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Cuboid::new(length, WALL_HEIGHT, WALL_THICKNESS)),
+                material: material.clone(),
+                transform,
+                ..default()
+            },
+            Collider::cuboid(length * 0.5, WALL_HEIGHT * 0.5, WALL_THICKNESS * 0.5),
+            Obstacle,
+            Solid,
+            ArenaWall,
+            ArenaScoped,
+            Name::new("Arena Wall"),
+        ));
+    }
+}
+
+// C moves the camera on to the arena's next camera preset
+pub fn cycle_camera_presets(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    arena: Res<CurrentArena>,
+    mut camera_preset: ResMut<CameraPresetIndex>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+) {
+    let presets = &arena.0.cameras;
+    if !keyboard_input.just_pressed(KeyCode::KeyC) || presets.is_empty() {
+        return;
+    }
+    camera_preset.0 = (camera_preset.0 + 1) % presets.len();
+    let preset = &presets[camera_preset.0];
+    info!("camera: {}", preset.name);
+    for mut transform in camera_query.iter_mut() {
+        *transform = preset.transform();
+    }
+}
+
+// how far something's collider reaches out from its middle across the floor
+fn body_radius(collider: Option<&Collider>) -> f32 {
+    collider.map_or(0.0, |collider| {
+        let half_extents = collider.raw.compute_local_aabb().half_extents();
+        half_extents.x.max(half_extents.z)
+    })
+}
+
+// Deals with everything that has got past the edge of the arena, each according to its
+// OutOfBounds. Only things that move can get out, so the spatial hash narrows it down to the
+// ones near the edge.
+#[allow(clippy::type_complexity)]
+pub fn enforce_arena_bounds(
+    mut commands: Commands,
+    bounds: Res<ArenaBounds>,
+    spatial_hash: Res<SpatialHash>,
+    mut query: Query<
+        (
+            &mut Transform,
+            &OutOfBounds,
+            Option<&mut Velocity>,
+            Option<&mut Health>,
+            Option<&Collider>,
+        ),
+        Without<Parent>,
+    >,
+) {
+    let centre = bounds.centre();
+    let safe_radius = (bounds.inner_radius() - HASH_SLACK).max(0.0);
+    for (entity, _) in spatial_hash.beyond(Vec3::new(centre.x, 0.0, centre.y), safe_radius) {
+        let Ok((mut transform, policy, velocity, health, collider)) = query.get_mut(entity) else {
+            continue;
+        };
+        // things the wall stops keep their whole body inside, the rest go once their middle is out
+        let margin = match policy {
+            OutOfBounds::Clamp | OutOfBounds::Bounce => body_radius(collider),
+            OutOfBounds::Kill | OutOfBounds::Despawn => 0.0,
+        };
+        let Some((inside, normal)) = bounds.push_inside(transform.translation, margin) else {
+            continue;
+        };
+
+        match policy {
+            OutOfBounds::Clamp | OutOfBounds::Bounce => {
+                transform.translation = inside;
+                let Some(mut velocity) = velocity else {
+                    continue;
+                };
+                let into_wall = velocity.value.dot(normal).min(0.0);
+                // clamping takes away the speed going into the wall, bouncing turns it around
+                let rebound = if *policy == OutOfBounds::Bounce {
+                    2.0
+                } else {
+                    1.0
+                };
+                velocity.value -= normal * into_wall * rebound;
+            }
+            OutOfBounds::Kill => match health {
+                Some(mut health) => health.value = 0.0,
+                None => commands.entity(entity).despawn_recursive(),
+            },
+            OutOfBounds::Despawn => commands.entity(entity).despawn_recursive(),
+        }
+    }
+}
+
+fn isometry(translation: Vec3, rotation: Quat) -> Isometry3<f32> {
+    Isometry3::from_parts(
+        Translation3::from(translation),
+        UnitQuaternion::from(rotation),
+    )
+}
+
+// Robots (and anything else that moves and has a collider) can't drive into anything solid. Each
+// one that has got inside is pushed straight back out and loses the part of its speed going in, the
+// same as driving into the arena's wall.
+#[allow(clippy::type_complexity)]
+pub fn push_out_of_solids(
+    solid_query: Query<(&GlobalTransform, &Collider), With<Solid>>,
+    mut mover_query: Query<
+        (&mut Transform, &Collider, &mut Velocity),
+        (Without<Solid>, Without<Parent>, Without<Bullet>),
+    >,
+    spatial_hash: Res<SpatialHash>,
+) {
+    for (solid_transform, solid_collider) in solid_query.iter() {
+        let (_, rotation, centre) = solid_transform.to_scale_rotation_translation();
+        let solid_isometry = isometry(centre, rotation);
+        let reach = solid_collider
+            .raw
+            .compute_local_aabb()
+            .half_extents()
+            .norm();
+        for (entity, _) in spatial_hash.within(centre, reach + HASH_SLACK) {
+            let Ok((mut transform, collider, mut velocity)) = mover_query.get_mut(entity) else {
+                continue;
+            };
+            let Ok(Some(touching)) = contact(
+                &solid_isometry,
+                &*solid_collider.raw,
+                &isometry(transform.translation, transform.rotation),
+                &*collider.raw,
+                0.0,
+            ) else {
+                continue;
+            };
+            if touching.dist >= 0.0 {
+                continue;
+            }
+            // robots stay on the floor, so they only get pushed sideways. Something sat right in
+            // the middle of a solid is pushed out whichever way is away from its centre.
+            let normal = Vec3::from(touching.normal1.into_inner()).with_y(0.0);
+            let normal = normal.try_normalize().unwrap_or_else(|| {
+                (transform.translation - centre)
+                    .with_y(0.0)
+                    .normalize_or(Vec3::X)
+            });
+            transform.translation += normal * (SOLID_SKIN - touching.dist);
+            let into_solid = velocity.value.dot(normal).min(0.0);
+            velocity.value -= normal * into_solid;
+        }
+    }
+}
+
+// Cover that's run out of health breaks into pieces that fly off in every direction. Taking the
+// cover away is left to despawn_dead_entities like anything else that dies.
+pub fn break_cover(
+    mut commands: Commands,
+    query: Query<(&Health, &Cover, &GlobalTransform)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut rng = rand::thread_rng();
+    for (health, cover, transform) in query.iter() {
+        if health.value > 0.0 {
+            continue;
+        }
+        let (_, rotation, centre) = transform.to_scale_rotation_translation();
+        // the cover cut into a grid of chunks, each a little smaller than its share
+        let piece = cover.size / DEBRIS_SPLITS as f32;
+        let mesh = meshes.add(Cuboid::from_size(piece * 0.8));
+        let material = materials.add(cover.colour);
+        for x in 0..DEBRIS_SPLITS {
+            for y in 0..DEBRIS_SPLITS {
+                for z in 0..DEBRIS_SPLITS {
+                    let offset = (IVec3::new(x, y, z).as_vec3() + 0.5) * piece - cover.size * 0.5;
+                    let position = centre + rotation * offset;
+                    let outwards = (position - centre).with_y(0.0).normalize_or(Vec3::X);
+                    let velocity = outwards * DEBRIS_SPEED * rng.gen_range(0.5..1.5)
+                        + Vec3::Y * rng.gen_range(2.0..5.0);
+                    commands.spawn((
+                        PbrBundle {
+                            mesh: mesh.clone(),
+                            material: material.clone(),
+                            transform: Transform::from_translation(position)
+                                .with_rotation(rotation),
+                            ..default()
+                        },
+                        Velocity::new(velocity),
+                        Acceleration::new(Vec3::NEG_Y * GRAVITY),
+                        Debris {
+                            timer: Timer::from_seconds(DEBRIS_LIFETIME, TimerMode::Once),
+                            half_height: piece.y * 0.4,
+                        },
+                        ArenaScoped,
+                        Name::new("Debris"),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+// Debris falls until it hits the floor, slides to a stop there and shrinks away at the end
+pub fn settle_debris(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Debris, &mut Transform, &mut Velocity)>,
+    time: Res<Time>,
+) {
+    for (entity, mut debris, mut transform, mut velocity) in query.iter_mut() {
+        if debris.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        if transform.translation.y <= debris.half_height {
+            transform.translation.y = debris.half_height;
+            velocity.value.y = 0.0;
+            velocity.value *= (1.0 - DEBRIS_FRICTION * time.delta_seconds()).max(0.0);
+        }
+        let remaining = debris.timer.remaining_secs();
+        transform.scale = Vec3::splat((remaining / DEBRIS_SHRINK_TIME).min(1.0));
+    }
+}
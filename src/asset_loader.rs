@@ -1,22 +1,126 @@
-use bevy::prelude::*;
+use crate::states::{GameFlowEvent, GameState};
+use bevy::{
+    asset::{LoadState, LoadedFolder, RecursiveDependencyLoadState},
+    prelude::*,
+};
+pub const BLOCK_MODEL: &str = "test.glb#Scene0";
+
 #[derive(Resource, Debug, Default)]
 pub struct SceneAssets {
     pub block: Handle<Scene>,
+    pub enemy_archetypes: Handle<LoadedFolder>, // every archetype file in assets/enemies
+    pub prefabs: Handle<LoadedFolder>,          // every prefab file in assets/prefabs
+    pub arenas: Handle<LoadedFolder>,           // every arena file in assets/arenas
+}
+
+impl SceneAssets {
+    // every asset that has to be loaded before gameplay can start, with a name for the loading screen
+    pub fn required(&self) -> Vec<(&'static str, UntypedHandle)> {
+        vec![
+            ("block model", self.block.clone().untyped()),
+            ("enemy archetypes", self.enemy_archetypes.clone().untyped()),
+            ("prefabs", self.prefabs.clone().untyped()),
+            ("arenas", self.arenas.clone().untyped()),
+        ]
+    }
+}
+
+// How far along the Loading state is, filled in every frame while loading
+#[derive(Resource, Debug, Default)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub total: usize,
+    pub failed: Vec<String>,
+}
+
+impl LoadingProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
 }
 
 pub struct AssetLoaderPlugin;
 
 impl Plugin for AssetLoaderPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<SceneAssets>().add_systems(Startup, load_assets);
+        app.init_resource::<SceneAssets>()
+            .init_resource::<LoadingProgress>()
+            .add_systems(Startup, load_assets)
+            .add_systems(OnEnter(GameState::Loading), reset_loading_progress)
+            .add_systems(
+                Update,
+                check_asset_loading.run_if(in_state(GameState::Loading)),
+            );
+    }
+}
+
+fn load_assets(mut scene_assets: ResMut<SceneAssets>, asset_server: Res<AssetServer>) {
+    *scene_assets = SceneAssets {
+        block: asset_server.load(BLOCK_MODEL),
+        enemy_archetypes: asset_server.load_folder("enemies"),
+        prefabs: asset_server.load_folder("prefabs"),
+        arenas: asset_server.load_folder("arenas"),
     }
 }
 
-fn load_assets(
-    mut scene_assets: ResMut<SceneAssets>,
+fn reset_loading_progress(mut progress: ResMut<LoadingProgress>) {
+    *progress = LoadingProgress::default();
+}
+
+// Gameplay only starts once every required asset and everything it depends on has loaded.
+// Anything that fails is logged and listed on the loading screen instead of failing silently.
+fn check_asset_loading(
     asset_server: Res<AssetServer>,
+    scene_assets: Res<SceneAssets>,
+    mut progress: ResMut<LoadingProgress>,
+    mut flow_events: EventWriter<GameFlowEvent>,
 ) {
-    *scene_assets = SceneAssets {
-        block: asset_server.load("test.glb#Scene0"),
+    let required = scene_assets.required();
+    let mut loaded = 0;
+    let mut failed = Vec::new();
+    for (name, handle) in required.iter() {
+        match asset_load_state(&asset_server, handle) {
+            Ok(true) => loaded += 1,
+            Ok(false) => (),
+            Err(reason) => failed.push(format!("{name}: {reason}")),
+        }
+    }
+
+    for failure in failed.iter() {
+        if !progress.failed.contains(failure) {
+            error!("failed to load {failure}");
+        }
+    }
+    *progress = LoadingProgress {
+        loaded,
+        total: required.len(),
+        failed,
+    };
+
+    if progress.loaded == progress.total && progress.failed.is_empty() {
+        flow_events.send(GameFlowEvent::LoadingFinished);
     }
-}
\ No newline at end of file
+}
+
+// Ok(true) when the asset is ready, Ok(false) while it's still loading and Err when it failed
+fn asset_load_state(asset_server: &AssetServer, handle: &UntypedHandle) -> Result<bool, String> {
+    // labelled assets like "test.glb#Scene0" only report failures on the file they come from
+    let file = handle
+        .path()
+        .and_then(|path| asset_server.get_path_id(path.without_label()));
+    for id in std::iter::once(handle.id()).chain(file) {
+        if let LoadState::Failed(error) = asset_server.load_state(id) {
+            return Err(error.to_string());
+        }
+    }
+
+    match asset_server.recursive_dependency_load_state(handle.id()) {
+        RecursiveDependencyLoadState::Loaded => Ok(true),
+        RecursiveDependencyLoadState::Failed => Err("a dependency failed to load".to_string()),
+        _ => Ok(false),
+    }
+}
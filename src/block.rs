@@ -1,57 +1,129 @@
 use crate::{
+    asset_loader::BLOCK_MODEL,
     // collision_detector::CollisionDamage,
-    combat::{CollisionDamage, Health},
-    entities::{Block, Dummy},
-    movement::{Acceleration, MovingObjBundle, Velocity},
+    combat::{HitZoneKind, Team, Weapon},
+    prefabs::{
+        Prefab, PrefabCollider, PrefabHitZone, PrefabModel, PrefabOverrides, PrefabRegistry,
+        PrefabRole, PrefabSpawner,
+    },
+    scene_collider::ColliderGen,
     schedule::InGameSet,
-    states::GameState,
-    asset_loader::SceneAssets,
+    settings::dev_tools_enabled,
 };
-use bevy::prelude::*;
-use bevy_rapier3d::prelude::{Collider, Sensor};
+use bevy::{ecs::system::EntityCommands, prelude::*};
 use rand::Rng;
 use std::ops::Range;
 
-const VELOCITY_SCALAR: f32 = 5.0;
-const ACCELERATION_SCALAR: f32 = 1.0;
 const SPAWN_RANGE_X: Range<f32> = -5.0..5.0;
 const SPAWN_RANGE_Z: Range<f32> = 0.0..5.0;
+
+// the parts of the block that can be hit, the front of the block is +z since that's the way it
+// drives
+const BLOCK_HIT_ZONES: [PrefabHitZone; 5] = [
+    PrefabHitZone {
+        kind: HitZoneKind::FrontArmor,
+        multiplier: 0.5,
+        centre: Vec3::new(0.0, 0.0, 0.25),
+        half_size: Vec3::new(0.45, 0.15, 0.1),
+    },
+    PrefabHitZone {
+        kind: HitZoneKind::Rear,
+        multiplier: 2.0,
+        centre: Vec3::new(0.0, 0.0, -0.25),
+        half_size: Vec3::new(0.45, 0.15, 0.1),
+    },
+    PrefabHitZone {
+        kind: HitZoneKind::Wheels,
+        multiplier: 1.0,
+        centre: Vec3::new(0.4, -0.05, 0.0),
+        half_size: Vec3::new(0.05, 0.1, 0.15),
+    },
+    PrefabHitZone {
+        kind: HitZoneKind::Wheels,
+        multiplier: 1.0,
+        centre: Vec3::new(-0.4, -0.05, 0.0),
+        half_size: Vec3::new(0.05, 0.1, 0.15),
+    },
+    PrefabHitZone {
+        kind: HitZoneKind::Weapon,
+        multiplier: 1.0,
+        centre: Vec3::new(0.0, 0.15, 0.0),
+        half_size: Vec3::new(0.1, 0.05, 0.15),
+    },
+];
+
 pub struct BlockPlugin;
 
 impl Plugin for BlockPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PostStartup, spawn_block)
-            .add_systems(OnEnter(GameState::GameOver), spawn_block)
-            .add_systems(Update, spawn_dummy.in_set(InGameSet::UserInput))
-            .add_systems(Update, block_destroyed.in_set(InGameSet::EntityUpdates));
+        // spawning dummies by hand is a debugging tool, turn it on with dev tools in the settings
+        app.add_systems(Startup, register_block_prefab).add_systems(
+            Update,
+            spawn_dummy
+                .run_if(dev_tools_enabled)
+                .in_set(InGameSet::UserInput),
+        );
     }
 }
 
-// now that we have our block bundle let's spawn in the block!
+// now that we have our block bundle let's describe the block as a prefab so anything can spawn it!
 
-pub fn spawn_block(
-    mut commands: Commands, // this includes the spawn command that we'll use
-    scene_assets: Res<SceneAssets>,
-) {
-    commands.spawn((
-        MovingObjBundle {
-            velocity: Velocity::new(Vec3::ZERO), // recall our functions we made this will just
-            acceleration: Acceleration::new(Vec3::ZERO), // make a '0.0' 3d vector like this (0.0, 0.0, 0.0)
-            health: Health::new(1000.0),
-            collider: Collider::cuboid(0.1, 0.1, 0.1),
-            collision: CollisionDamage(100.0),
-            // this will spawn the block at the xyz coords (0.0, 0.5, 0.0)
-        }, // the default sets the rest of the PbrBundle components to
-        SceneBundle {
-            scene: scene_assets.block.clone(),
-            transform: Transform::from_xyz(0.0, 0.5, 0.0),
-            ..default()
+fn block_prefab() -> Prefab {
+    Prefab {
+        name: "Player".to_string(),
+        role: PrefabRole::Player, // this gives it the Block component, see below for why
+        // the block's body starts off as a compound collider made up of all of its hit zones, once
+        // the model has loaded it's replaced with one generated from the model itself
+        model: PrefabModel::Scene {
+            path: BLOCK_MODEL.to_string(),
+            collider: Some(ColliderGen::ConvexHull),
         },
-        // a built in default variable
-        Block, // <---- see below for why this is here
-        Name::new("Player"),
-        Sensor,
-    ));
+        collider: PrefabCollider::HitZones,
+        sensor: true,
+        health: 1000.0,
+        collision_damage: 100.0,
+        mass: 10.0,
+        team: Some(Team::Player),
+        ramming: Some(6.0),
+        status_effects: true,
+        weapons: vec![
+            Weapon {
+                projectile: "bullet".to_string(),
+                fire_rate: 8.0,
+                spread: 0.02,
+                range: 20.0,
+                cooldown: 0.0,
+            },
+            Weapon {
+                projectile: "shock_round".to_string(),
+                fire_rate: 1.0,
+                spread: 0.0,
+                range: 15.0,
+                cooldown: 0.0,
+            },
+            Weapon {
+                projectile: "rocket".to_string(),
+                fire_rate: 0.5,
+                spread: 0.0,
+                range: 20.0,
+                cooldown: 0.0,
+            },
+        ],
+        hit_zones: BLOCK_HIT_ZONES.to_vec(),
+        ..default()
+    }
+}
+
+fn register_block_prefab(mut registry: ResMut<PrefabRegistry>) {
+    registry.register("block", block_prefab());
+}
+
+// spawns the player's block at translation, normally (0.0, 0.5, 0.0)
+pub fn spawn_block<'a>(
+    prefabs: &'a mut PrefabSpawner,
+    translation: Vec3,
+) -> Option<EntityCommands<'a>> {
+    prefabs.spawn("block", PrefabOverrides::at(translation))
 }
 
 // This looks similar to the original spawn block command with the difference being we have given
@@ -70,12 +142,7 @@ pub fn spawn_block(
 
 //This tells the program the spawned block is a "Block" entity and will not confuse it with something else
 
-fn spawn_dummy(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-) {
+fn spawn_dummy(mut prefabs: PrefabSpawner, keyboard_input: Res<ButtonInput<KeyCode>>) {
     let mut rng = rand::thread_rng();
     let translation = Vec3::new(
         rng.gen_range(SPAWN_RANGE_X),
@@ -83,55 +150,11 @@ fn spawn_dummy(
         rng.gen_range(SPAWN_RANGE_Z),
     );
 
-    let mut random_unit_vector =
-        || Vec3::new(rng.gen_range(-1.0..1.0), 0., rng.gen_range(-1.0..1.0)).normalize_or_zero();
-    let velocity = random_unit_vector() * VELOCITY_SCALAR;
-    let acceleration = random_unit_vector() * ACCELERATION_SCALAR;
-
-    if keyboard_input.pressed(KeyCode::KeyJ) {
-        commands.spawn((
-            MovingObjBundle {
-                velocity: Velocity::new(velocity), // recall our functions we made this will just
-                acceleration: Acceleration::new(acceleration), // make a '0.0' 3d vector like this (0.0, 0.0, 0.0)
-                health: Health::new(100.0),
-                collider: Collider::cuboid(0.5, 0.5, 0.5),
-                collision: CollisionDamage(35.0),
-                // this will spawn the block at the xyz coords (0.0, 0.5, 0.0)
-            }, // the default sets the rest of the PbrBundle components to
-            PbrBundle {
-                mesh: meshes.add(Cuboid::new(1.0, 1.0, 1.0)), // makes the cube
-                material: materials.add(Color::srgb(255.0, 0.0, 0.0)), // makes the cube black
-                transform: Transform::from_translation(translation),
-                ..default() // Transform describes the position of the block
-            },
-            // a built in default variable
-            Dummy, // <---- see below for why this is here
-            Name::new("Dummy Block"),
-        ));
-    }
-    if keyboard_input.pressed(KeyCode::KeyK) {
-        commands.spawn((
-            MovingObjBundle {
-                velocity: Velocity::new(Vec3::ZERO),
-                acceleration: Acceleration::new(Vec3::ZERO),
-                health: Health::new(10000.0),
-                collider: Collider::cuboid(0.5,0.5,0.5),
-                collision: CollisionDamage(35.0),
-            },
-            PbrBundle {
-                mesh: meshes.add(Cuboid::new(1.0, 1.0, 1.0)), // makes the cube
-                material: materials.add(Color::srgb(255.0, 0.0, 0.0)), // makes the cube black
-                transform: Transform::from_xyz(5.0,0.5,5.0),
-                ..default() // Transform describes the position of the block
-            },
-            Dummy,
-            Name::new("Fixed Block"),
-        ));
+    // what the dummies look like and how they behave comes from assets/enemies
+    if keyboard_input.just_pressed(KeyCode::KeyJ) {
+        prefabs.spawn("roaming_dummy", PrefabOverrides::at(translation));
     }
-}
-
-fn block_destroyed(mut next_state: ResMut<NextState<GameState>>, query: Query<(), With<Block>>) {
-    if query.get_single().is_err() {
-        next_state.set(GameState::GameOver);
+    if keyboard_input.just_pressed(KeyCode::KeyK) {
+        prefabs.spawn("fixed_dummy", PrefabOverrides::at(Vec3::new(5.0, 0.5, 5.0)));
     }
 }
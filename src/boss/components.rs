@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+// A boss fight, written out in the boss section of an .enemy.ron file. The fight is split into
+// phases and every phase is a timeline: a list of things to do and when to do them. The boss
+// works through the timeline and starts it again from the top once it's run out.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossScript {
+    pub phases: Vec<BossPhase>,
+}
+
+// The boss switches to a phase once its health drops to health_below (a fraction of the health
+// it started with), so a phase with 0.5 kicks in at half health. The first phase should be 1.0.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossPhase {
+    pub name: String,
+    pub health_below: f32,
+    pub duration: f32, // how long the timeline runs before starting again
+    pub timeline: Vec<TimelineEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimelineEntry {
+    pub at: f32, // seconds after the start of the timeline
+    pub action: BossAction,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum BossAction {
+    // fires count projectiles spread evenly all the way round, offset (in degrees) turns the ring
+    // so two rings in a row can leave gaps in different places
+    BulletRing {
+        projectile: String,
+        count: u32,
+        #[serde(default)]
+        offset: f32,
+    },
+    // dashes in a straight line through its target, carrying on overshoot past it
+    Charge {
+        speed: f32,
+        overshoot: f32,
+    },
+    // calls in count enemies, spaced out in a circle of radius around the boss
+    Summon {
+        enemy: String,
+        count: u32,
+        radius: f32,
+    },
+    // soaks up absorb (0.0 to 1.0) of every hit for duration seconds
+    Shield {
+        duration: f32,
+        absorb: f32,
+    },
+}
+
+// Plays a BossScript. Needs the steering AI too, the boss walks around with that between attacks
+// and uses what it perceives to know where to charge.
+#[derive(Component, Debug)]
+pub struct BossPatterns {
+    pub script: BossScript,
+    pub phase: usize, // index of the phase it's in
+    pub clock: f32,   // seconds into the phase's timeline
+    pub next_entry: usize,
+}
+
+impl BossPatterns {
+    pub fn new(mut script: BossScript) -> Self {
+        // phases go from the highest health down and timelines from the earliest entry on
+        script
+            .phases
+            .sort_by(|a, b| b.health_below.total_cmp(&a.health_below));
+        for phase in script.phases.iter_mut() {
+            phase.timeline.sort_by(|a, b| a.at.total_cmp(&b.at));
+        }
+        Self {
+            script,
+            phase: 0,
+            clock: 0.0,
+            next_entry: 0,
+        }
+    }
+
+    pub fn phase_name(&self) -> &str {
+        self.script
+            .phases
+            .get(self.phase)
+            .map_or("", |phase| phase.name.as_str())
+    }
+}
@@ -0,0 +1,6 @@
+mod components;
+mod plugins;
+mod systems;
+
+pub use components::*;
+pub use plugins::*;
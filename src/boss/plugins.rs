@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+
+use crate::schedule::InGameSet;
+
+use super::systems::*;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, run_boss_patterns.in_set(InGameSet::EntityUpdates));
+    }
+}
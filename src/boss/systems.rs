@@ -0,0 +1,170 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::AiBrain,
+    arena::ArenaBounds,
+    combat::{fire_weapon, Health, NoiseEvent, Shield, StatusEffects, Team, Weapon},
+    navigation::{FollowPath, NavGrid},
+    prefabs::{PrefabOverrides, PrefabSpawner},
+    waves::WaveEnemy,
+};
+
+use super::*;
+
+// the shortest a timeline can be, so a phase with a duration of 0.0 can't loop forever in a frame
+const MIN_TIMELINE_DURATION: f32 = 0.1;
+// charges and minions near the wall are kept at least this far inside it
+const ARENA_MARGIN: f32 = 1.5;
+
+// The pattern executor. Every frame it checks whether the boss has dropped into its next phase,
+// moves the phase's clock on and carries out every action on the timeline that has come due.
+// A stunned boss's clock stops, and EMP stops it firing.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn run_boss_patterns(
+    mut commands: Commands,
+    mut prefabs: PrefabSpawner,
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut boss_query: Query<(
+        Entity,
+        &mut BossPatterns,
+        &AiBrain,
+        &Transform,
+        &Health,
+        Option<&Team>,
+        Option<&StatusEffects>,
+        Has<WaveEnemy>,
+    )>,
+    target_query: Query<&GlobalTransform>,
+    grid: Res<NavGrid>,
+    bounds: Res<ArenaBounds>,
+    time: Res<Time>,
+) {
+    for (entity, mut patterns, brain, transform, health, team, status_effects, in_wave) in
+        boss_query.iter_mut()
+    {
+        if patterns.script.phases.is_empty() {
+            continue;
+        }
+        // phases only ever go forwards, healing the boss doesn't take it back to an easier one
+        let health_fraction = health.value / brain.max_health.max(f32::EPSILON);
+        while let Some(next_phase) = patterns.script.phases.get(patterns.phase + 1) {
+            if health_fraction > next_phase.health_below {
+                break;
+            }
+            info!("{entity:?} enters boss phase {}", next_phase.name);
+            patterns.phase += 1;
+            patterns.clock = 0.0;
+            patterns.next_entry = 0;
+        }
+
+        if status_effects.is_some_and(|status_effects| status_effects.is_stunned()) {
+            continue;
+        }
+        patterns.clock += time.delta_seconds();
+
+        let phase = &patterns.script.phases[patterns.phase];
+        let due: Vec<BossAction> = phase.timeline[patterns.next_entry..]
+            .iter()
+            .take_while(|entry| entry.at <= patterns.clock)
+            .map(|entry| entry.action.clone())
+            .collect();
+        let duration = phase.duration.max(MIN_TIMELINE_DURATION);
+        patterns.next_entry += due.len();
+        if patterns.clock >= duration {
+            patterns.clock -= duration;
+            patterns.next_entry = 0;
+        }
+
+        let position = transform.translation;
+        // where the boss thinks its target is, if it knows at all
+        let target = brain
+            .target
+            .and_then(|target| target_query.get(target).ok())
+            .map(|target_transform| target_transform.translation())
+            .or(brain.last_known);
+        let weapons_disabled =
+            status_effects.is_some_and(|status_effects| status_effects.weapons_disabled());
+
+        for action in due {
+            match action {
+                BossAction::BulletRing {
+                    projectile,
+                    count,
+                    offset,
+                } => {
+                    if weapons_disabled {
+                        continue;
+                    }
+                    // a throwaway weapon for every shot so the cooldown never gets in the way
+                    let weapon = Weapon {
+                        projectile,
+                        fire_rate: 1.0,
+                        spread: 0.0,
+                        range: 0.0,
+                        cooldown: 0.0,
+                    };
+                    for shot in 0..count {
+                        let angle = offset.to_radians() + TAU * shot as f32 / count as f32;
+                        let direction = Quat::from_rotation_y(angle) * Vec3::Z;
+                        fire_weapon(
+                            &mut prefabs,
+                            &mut noise_events,
+                            &mut weapon.clone(),
+                            entity,
+                            position,
+                            direction,
+                            team.copied(),
+                        );
+                    }
+                }
+                BossAction::Charge { speed, overshoot } => {
+                    let Some(target) = target else {
+                        continue;
+                    };
+                    // a charge is just a one waypoint path, which takes the driving away from the
+                    // steering AI until it's over
+                    let direction = (target - position).with_y(0.0).normalize_or_zero();
+                    // stopping short of the wall, a waypoint behind it could never be reached
+                    let end = bounds.clamp(
+                        (target + direction * overshoot).with_y(position.y),
+                        ARENA_MARGIN,
+                    );
+                    commands.entity(entity).insert(FollowPath {
+                        waypoints: vec![end],
+                        next: 0,
+                        speed,
+                        grid_version: grid.version,
+                    });
+                }
+                BossAction::Summon {
+                    enemy,
+                    count,
+                    radius,
+                } => {
+                    for minion in 0..count {
+                        let angle = TAU * minion as f32 / count.max(1) as f32;
+                        let offset = Quat::from_rotation_y(angle) * Vec3::Z * radius;
+                        let overrides = PrefabOverrides {
+                            team: team.copied(),
+                            ..PrefabOverrides::at(bounds.clamp(position + offset, ARENA_MARGIN))
+                        };
+                        let Some(mut spawned) = prefabs.spawn(&enemy, overrides) else {
+                            break;
+                        };
+                        // minions of a wave's boss have to be beaten before the wave is over too
+                        if in_wave {
+                            spawned.insert(WaveEnemy);
+                        }
+                    }
+                }
+                BossAction::Shield { duration, absorb } => {
+                    commands
+                        .entity(entity)
+                        .insert(Shield::new(absorb, duration));
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
 pub use bevy::prelude::*;
+use bevy_rapier3d::prelude::{Collider, Sensor};
+use serde::Deserialize;
 
 #[derive(Component, Debug)]
 pub struct CollisionDamage(pub f32);
@@ -13,3 +15,234 @@ impl Health {
         Self { value }
     }
 }
+
+// Takes away this fraction of every hit before it reaches Health, 0.0 is no armor and 1.0 blocks
+// everything
+#[derive(Component, Debug)]
+pub struct Armor(pub f32);
+
+// Which side an entity fights for, things on the same team don't hurt each other
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Reflect)]
+pub enum Team {
+    Player,
+    Enemy,
+}
+
+// Robots with this component deal ramming damage when they run into each other. The damage is
+// worked out from how fast they hit, and the slower or lighter robot takes the bigger share.
+#[derive(Component, Debug)]
+pub struct Ramming {
+    pub damage_per_speed: f32,
+}
+
+impl Ramming {
+    pub fn new(damage_per_speed: f32) -> Self {
+        Self { damage_per_speed }
+    }
+}
+
+// Status effects are timed debuffs that change how other systems treat an entity
+
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub enum StatusEffectKind {
+    Slow { factor: f32 },            // scales how far the entity moves each frame
+    Stun,                            // no driving and no firing
+    Burn { damage_per_second: f32 }, // damage over time
+    Emp,                             // weapons are disabled
+}
+
+impl StatusEffectKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusEffectKind::Slow { .. } => "Slow",
+            StatusEffectKind::Stun => "Stun",
+            StatusEffectKind::Burn { .. } => "Burn",
+            StatusEffectKind::Emp => "EMP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    pub remaining: f32, // seconds left before the effect wears off
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusEffectKind, duration: f32) -> Self {
+        Self {
+            kind,
+            remaining: duration,
+        }
+    }
+}
+
+#[derive(Component, Debug, Default)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    // applying an effect the entity already has replaces it if the new one is stronger or lasts longer
+    pub fn apply(&mut self, effect: StatusEffect) {
+        let same_kind = self.effects.iter_mut().find(|active| {
+            std::mem::discriminant(&active.kind) == std::mem::discriminant(&effect.kind)
+        });
+        match same_kind {
+            Some(active) => {
+                active.remaining = active.remaining.max(effect.remaining);
+                active.kind = effect.kind;
+            }
+            None => self.effects.push(effect),
+        }
+    }
+
+    pub fn speed_factor(&self) -> f32 {
+        self.effects
+            .iter()
+            .map(|effect| match effect.kind {
+                StatusEffectKind::Slow { factor } => factor,
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.kind == StatusEffectKind::Stun)
+    }
+
+    pub fn weapons_disabled(&self) -> bool {
+        self.is_stunned()
+            || self
+                .effects
+                .iter()
+                .any(|effect| effect.kind == StatusEffectKind::Emp)
+    }
+}
+
+// Put this on a bullet or hazard to hand out status effects to whatever it damages
+#[derive(Component, Debug)]
+pub struct StatusOnHit(pub Vec<StatusEffect>);
+
+// A projectile with a blast goes off when it hits, hurting everything else within radius too. The
+// damage falls off to nothing at the edge of the blast.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+pub struct Blast {
+    pub radius: f32,
+    pub damage: f32,
+}
+
+// A gun that fires a projectile prefab. The player and the AI both fire through fire_weapon, so
+// tweaking a weapon or its projectile changes it for both sides.
+#[derive(Debug, Clone, Deserialize, Reflect)]
+pub struct Weapon {
+    pub projectile: String, // id of the prefab it fires, e.g. "bullet"
+    pub fire_rate: f32,     // shots per second
+    pub spread: f32,        // shots go up to this many radians either side of where it's aimed
+    pub range: f32,         // the AI won't shoot at anything further away than this
+    #[serde(skip)]
+    pub cooldown: f32, // seconds until it can fire again
+}
+
+// every weapon a robot has, the player fires the first with F and the second with G
+#[derive(Component, Debug, Clone, Default, Reflect)]
+pub struct Weapons(pub Vec<Weapon>);
+
+// Hit zones split a robot up into parts that each take a different amount of damage. Every zone
+// is its own sensor collider parented to the robot so we can tell exactly which part got hit.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Reflect)]
+pub enum HitZoneKind {
+    FrontArmor,
+    Rear,
+    Wheels,
+    Weapon,
+}
+
+impl HitZoneKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HitZoneKind::FrontArmor => "Front Armor",
+            HitZoneKind::Rear => "Rear",
+            HitZoneKind::Wheels => "Wheels",
+            HitZoneKind::Weapon => "Weapon",
+        }
+    }
+
+    // extra effects a hit on this part causes, shooting the wheels slows you down and shooting the
+    // weapon knocks it out for a bit
+    pub fn effects(&self) -> Vec<StatusEffect> {
+        match self {
+            HitZoneKind::Wheels => vec![StatusEffect::new(
+                StatusEffectKind::Slow { factor: 0.6 },
+                2.0,
+            )],
+            HitZoneKind::Weapon => vec![StatusEffect::new(StatusEffectKind::Emp, 2.0)],
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HitZone {
+    pub kind: HitZoneKind,
+    pub multiplier: f32,
+}
+
+#[derive(Bundle)]
+pub struct HitZoneBundle {
+    pub zone: HitZone,
+    pub collider: Collider,
+    pub sensor: Sensor,
+    pub transform: TransformBundle,
+    pub name: Name,
+}
+
+impl HitZoneBundle {
+    pub fn new(kind: HitZoneKind, multiplier: f32, collider: Collider, translation: Vec3) -> Self {
+        Self {
+            zone: HitZone { kind, multiplier },
+            collider,
+            sensor: Sensor,
+            transform: TransformBundle::from_transform(Transform::from_translation(translation)),
+            name: Name::new(kind.name()),
+        }
+    }
+}
+
+// Entities with this can't take damage until the timer runs out, the player gets it after respawning
+#[derive(Component, Debug)]
+pub struct Invulnerable {
+    pub timer: Timer,
+}
+
+impl Invulnerable {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+}
+
+// Soaks up a fraction of every hit until the timer runs out, on top of any Armor. A see-through
+// bubble shows while it's up.
+#[derive(Component, Debug)]
+pub struct Shield {
+    pub absorb: f32, // from 0.0 (does nothing) to 1.0 (blocks everything)
+    pub timer: Timer,
+}
+
+impl Shield {
+    pub fn new(absorb: f32, duration: f32) -> Self {
+        Self {
+            absorb,
+            timer: Timer::from_seconds(duration.max(0.0), TimerMode::Once),
+        }
+    }
+}
+
+// the bubble shown around a shielded entity
+#[derive(Component, Debug)]
+pub struct ShieldBubble;
@@ -0,0 +1,26 @@
+use bevy::prelude::*;
+
+use super::{HitZoneKind, StatusEffect, Team};
+
+// Every bit of damage in the game goes through this event, so anything that reacts to being hit
+// (health, knockback, ui) only has to listen in one place
+
+#[derive(Event, Debug, Clone)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: Entity,
+    pub amount: f32,
+    pub direction: Vec3, // the direction the hit travels in, used for knockback
+    pub effects: Vec<StatusEffect>, // status effects the hit applies to the target
+    pub zone: Option<HitZoneKind>, // which part of the target was hit, if it has hit zones
+}
+
+// Something loud happened, like a gun going off. AI robots within earshot go and have a look even
+// if they can't see who made it.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct NoiseEvent {
+    pub source: Entity,
+    pub position: Vec3,
+    pub radius: f32, // how far away it can be heard
+    pub team: Option<Team>,
+}
@@ -1,6 +1,12 @@
 mod components;
+mod events;
 mod plugins;
+mod resources;
 mod systems;
+mod weapons;
 
 pub use components::*;
+pub use events::*;
 pub use plugins::*;
+pub use resources::*;
+pub use weapons::*;
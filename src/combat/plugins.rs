@@ -3,12 +3,39 @@ use bevy::prelude::*;
 use crate::schedule::InGameSet;
 
 use super::systems::*;
+use super::{tick_weapon_cooldowns, DamageEvent, NoiseEvent, RamContacts, Weapons};
 
 pub struct CombatManagementPlugin;
 
 impl Plugin for CombatManagementPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Update, apply_collision_damage.in_set(InGameSet::CollisionDetection))
-        .add_systems(Update, block_weapons.in_set(InGameSet::UserInput));
+        app.register_type::<Weapons>()
+            .add_event::<DamageEvent>()
+            .add_event::<NoiseEvent>()
+            .init_resource::<RamContacts>()
+            .add_systems(Startup, register_projectile_prefabs)
+            .add_systems(Update, enable_fixed_collisions)
+            .add_systems(
+                Update,
+                (
+                    apply_collision_damage,
+                    apply_ramming_damage,
+                    (apply_damage, apply_knockback, apply_status_effects),
+                )
+                    .chain()
+                    .in_set(InGameSet::CollisionDetection),
+            )
+            .add_systems(
+                Update,
+                (
+                    tick_status_effects,
+                    tick_invulnerability,
+                    tick_weapon_cooldowns,
+                    show_shields,
+                    tick_shields,
+                )
+                    .in_set(InGameSet::EntityUpdates),
+            )
+            .add_systems(Update, block_weapons.in_set(InGameSet::UserInput));
     }
 }
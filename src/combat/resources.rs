@@ -0,0 +1,8 @@
+use bevy::{prelude::*, utils::HashSet};
+
+// The pairs of ramming robots that were touching last frame, so we only deal ramming damage once
+// when they first make contact instead of every frame they stay pushed together.
+#[derive(Resource, Debug, Default)]
+pub struct RamContacts {
+    pub pairs: HashSet<(Entity, Entity)>,
+}
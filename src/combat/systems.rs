@@ -1,75 +1,585 @@
 use crate::{
-    entities::{Block, Bullet, Dummy},
-    movement::MovingObjBundle,
+    arena::Solid,
+    entities::{Block, Bullet},
+    prefabs::{Prefab, PrefabCollider, PrefabModel, PrefabRegistry, PrefabRole, PrefabSpawner},
+    spatial::SpatialHash,
+    stats::MatchStats,
 };
 
 use super::*;
 use crate::movement::*;
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
 use bevy_rapier3d::{
+    na::Isometry3,
     plugin::RapierContext,
-    prelude::{Collider, Sensor},
+    prelude::{ActiveCollisionTypes, Collider, ExternalImpulse},
 };
 
+// how much knockback impulse one point of damage is worth
+const KNOCKBACK_PER_DAMAGE: f32 = 0.5;
+// the shield bubble sits this far out from the corners of whatever it's around, and is this big
+// around anything without a collider yet
+const SHIELD_BUBBLE_MARGIN: f32 = 0.05;
+const SHIELD_BUBBLE_RADIUS: f32 = 0.9;
+
+// None of our colliders have a rigid body, so rapier treats all of them as fixed and by default
+// never checks fixed colliders against each other. Turning every collision type on makes our
+// colliders actually report intersections.
+pub fn enable_fixed_collisions(mut commands: Commands, query: Query<Entity, Added<Collider>>) {
+    for entity in query.iter() {
+        commands
+            .entity(entity)
+            .try_insert(ActiveCollisionTypes::all());
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn apply_collision_damage(
-    collision_damage_query: Query<(Entity, &CollisionDamage)>,
-    mut health_query: Query<&mut Health>,
-    dummies_query: Query<&Dummy>,
+    collision_damage_query: Query<(
+        Entity,
+        &CollisionDamage,
+        &GlobalTransform,
+        Option<&Velocity>,
+        Option<&StatusOnHit>,
+    )>,
+    transform_query: Query<&GlobalTransform>,
+    collider_query: Query<(&GlobalTransform, &Collider)>,
+    zone_query: Query<(&HitZone, &Parent)>,
+    health_query: Query<(), With<Health>>,
+    team_query: Query<&Team>,
+    bullet_query: Query<(), With<Bullet>>,
+    ramming_query: Query<(), With<Ramming>>,
+    solid_query: Query<(), With<Solid>>,
+    blast_query: Query<&Blast>,
+    spatial_hash: Res<SpatialHash>,
     rapier_context: Res<RapierContext>,
-    name_query: Query<&Bullet>,
+    mut damage_events: EventWriter<DamageEvent>,
     mut commands: Commands,
+    time: Res<Time>,
 ) {
-    for (e, damage) in collision_damage_query.iter() {
-        for (collided, collider, _) in rapier_context.intersection_pairs_with(e)
-        //.filter(|(_, _, bool)| bool == &true)
-        {
-            // dummies should not damage other dummies
-            if dummies_query.get(collided).is_ok() && dummies_query.get(collider).is_ok() {
+    // nobody should damage their own team
+    let same_team = |first, second| match (team_query.get(first), team_query.get(second)) {
+        (Ok(first_team), Ok(second_team)) => first_team == second_team,
+        _ => false,
+    };
+
+    for (source, damage, source_transform, source_velocity, on_hit) in collision_damage_query.iter()
+    {
+        // rapier finds what's touching after everything has moved, so these are from where it was
+        // at the start of this frame. A step before that is where it was before it got to any of
+        // them, and whatever's closest to there is what it got to first.
+        let step =
+            source_velocity.map_or(Vec3::ZERO, |velocity| velocity.value) * time.delta_seconds();
+        let came_from = source_transform.translation() - step * 2.0;
+        let distance_from_start = |collider_entity| {
+            collider_query
+                .get(collider_entity)
+                .map_or(f32::MAX, |(transform, collider)| {
+                    let (_, rotation, translation) = transform.to_scale_rotation_translation();
+                    let isometry = Isometry3::from_parts(translation.into(), rotation.into());
+                    collider
+                        .raw
+                        .distance_to_point(&isometry, &came_from.into(), true)
+                })
+        };
+
+        // a hit can touch a robot's body and several of its zones at once, so gather everything
+        // first and only keep the zone that takes the most damage for each target, along with how
+        // close the closest part of it is
+        let mut hits: HashMap<Entity, (Option<HitZone>, f32)> = HashMap::new();
+        for (collider1, collider2, intersecting) in rapier_context.intersection_pairs_with(source) {
+            if !intersecting {
                 continue;
             }
-            let e_target = collided;
-            let Ok(mut health) = health_query.get_mut(e_target) else {
-                return;
+            let other = if collider1 == source {
+                collider2
+            } else {
+                collider1
+            };
+            let (target, zone) = match zone_query.get(other) {
+                Ok((zone, parent)) => (parent.get(), Some(*zone)),
+                Err(_) => (other, None),
             };
-            
-            health.value -= damage.0;
-            if name_query.get(collider).is_ok() {
-                commands.entity(collider).despawn_recursive();
+            if target == source {
+                continue;
+            }
+            let distance = distance_from_start(other);
+            let (best, nearest) = hits.entry(target).or_insert((zone, distance));
+            if zone.is_some_and(|zone| best.is_none_or(|best| zone.multiplier > best.multiplier)) {
+                *best = zone;
+            }
+            *nearest = nearest.min(distance);
+        }
+
+        let is_bullet = bullet_query.contains(source);
+        hits.retain(|target, _| {
+            let is_solid = solid_query.contains(*target);
+            !same_team(source, *target)
+                && (health_query.contains(*target) || is_solid)
+                // only bullets hurt cover, robots driving into it just get stopped
+                && (is_bullet || !is_solid)
+                // robots running into each other are handled by the ramming system instead
+                && !(ramming_query.contains(source) && ramming_query.contains(*target))
+        });
+        // a bullet stops on the first thing in its way, so a robot in front of cover gets hit and
+        // anything behind cover is safe
+        if is_bullet {
+            let first = hits
+                .iter()
+                .min_by(|(_, (_, a)), (_, (_, b))| a.total_cmp(b))
+                .map(|(target, _)| *target);
+            hits.retain(|target, _| Some(*target) == first);
+        }
+        for (target, (zone, _)) in hits {
+            // moving things push along their path, anything else pushes away from itself
+            let direction = match source_velocity {
+                Some(velocity) if velocity.value.length_squared() > f32::EPSILON => velocity.value,
+                _ => transform_query
+                    .get(target)
+                    .map_or(Vec3::ZERO, |target_transform| {
+                        target_transform.translation() - source_transform.translation()
+                    }),
+            };
+
+            let mut effects = on_hit.map_or(Vec::new(), |on_hit| on_hit.0.clone());
+            effects.extend(zone.iter().flat_map(|zone| zone.kind.effects()));
+            // walls and plain obstacles can't be hurt, but the bullet still stops on them
+            if health_query.contains(target) {
+                damage_events.send(DamageEvent {
+                    target,
+                    source,
+                    amount: damage.0 * zone.map_or(1.0, |zone| zone.multiplier),
+                    direction,
+                    effects,
+                    zone: zone.map(|zone| zone.kind),
+                });
+            }
+
+            // a bullet is used up on the first thing it hits
+            if is_bullet {
+                if let Ok(blast) = blast_query.get(source) {
+                    // the spatial hash finds who's caught in the blast without checking everyone
+                    let centre = source_transform.translation();
+                    for (other, other_position) in spatial_hash.within(centre, blast.radius) {
+                        if other == source
+                            || other == target
+                            || same_team(source, other)
+                            || !health_query.contains(other)
+                        {
+                            continue;
+                        }
+                        let falloff = 1.0 - other_position.distance(centre) / blast.radius;
+                        damage_events.send(DamageEvent {
+                            target: other,
+                            source,
+                            amount: blast.damage * falloff,
+                            direction: other_position - centre,
+                            effects: Vec::new(),
+                            zone: None,
+                        });
+                    }
+                }
+                commands.entity(source).despawn_recursive();
+                break;
             }
         }
     }
 }
 
-pub fn block_weapons(
+// Ramming damage is only dealt on the frame two robots first touch. Each side takes damage from
+// the other based on their relative speed, split so that the lighter and the slower robot takes more.
+#[allow(clippy::type_complexity)]
+pub fn apply_ramming_damage(
+    ramming_query: Query<(
+        Entity,
+        &Ramming,
+        &GlobalTransform,
+        Option<&Velocity>,
+        Option<&Mass>,
+    )>,
+    team_query: Query<&Team>,
+    rapier_context: Res<RapierContext>,
+    mut ram_contacts: ResMut<RamContacts>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    let mut touching = HashSet::new();
+    for (entity, ..) in ramming_query.iter() {
+        let intersections = rapier_context
+            .intersection_pairs_with(entity)
+            .filter(|(_, _, intersecting)| *intersecting)
+            .map(|(collider1, collider2, _)| (collider1, collider2));
+        let contacts = rapier_context
+            .contact_pairs_with(entity)
+            .filter(|pair| pair.has_any_active_contact())
+            .map(|pair| (pair.collider1(), pair.collider2()));
+
+        for (collider1, collider2) in intersections.chain(contacts) {
+            let other = if collider1 == entity {
+                collider2
+            } else {
+                collider1
+            };
+            if !ramming_query.contains(other) {
+                continue;
+            }
+            // nobody should damage their own team
+            if let (Ok(team), Ok(other_team)) = (team_query.get(entity), team_query.get(other)) {
+                if team == other_team {
+                    continue;
+                }
+            }
+            touching.insert((entity.min(other), entity.max(other)));
+        }
+    }
+
+    for &(a, b) in touching.difference(&ram_contacts.pairs) {
+        let Ok([robot_a, robot_b]) = ramming_query.get_many([a, b]) else {
+            continue;
+        };
+        let (_, ram_a, transform_a, velocity_a, mass_a) = robot_a;
+        let (_, ram_b, transform_b, velocity_b, mass_b) = robot_b;
+
+        let velocity_a = velocity_a.map_or(Vec3::ZERO, |velocity| velocity.value);
+        let velocity_b = velocity_b.map_or(Vec3::ZERO, |velocity| velocity.value);
+        let mass_a = mass_a.map_or(1.0, |mass| mass.value.max(f32::EPSILON));
+        let mass_b = mass_b.map_or(1.0, |mass| mass.value.max(f32::EPSILON));
+
+        let impact_speed = (velocity_a - velocity_b).length();
+        if impact_speed <= f32::EPSILON {
+            continue;
+        }
+
+        let (share_a, share_b) =
+            ram_shares(mass_a, mass_b, velocity_a.length(), velocity_b.length());
+
+        let push_a = transform_a.translation() - transform_b.translation();
+        damage_events.send(DamageEvent {
+            target: a,
+            source: b,
+            amount: ram_b.damage_per_speed * impact_speed * share_a,
+            direction: push_a,
+            effects: Vec::new(),
+            zone: None,
+        });
+        damage_events.send(DamageEvent {
+            target: b,
+            source: a,
+            amount: ram_a.damage_per_speed * impact_speed * share_b,
+            direction: -push_a,
+            effects: Vec::new(),
+            zone: None,
+        });
+    }
+
+    ram_contacts.pairs = touching;
+}
+
+// How much of a ram each side takes, half from mass and half from speed, so the lighter and the
+// slower robot take more. The two shares always add up to 1.
+fn ram_shares(mass_a: f32, mass_b: f32, speed_a: f32, speed_b: f32) -> (f32, f32) {
+    let total_speed = speed_a + speed_b;
+    let speed_share_a = if total_speed > f32::EPSILON {
+        speed_b / total_speed
+    } else {
+        0.5
+    };
+    let share_a = 0.5 * (mass_b / (mass_a + mass_b) + speed_share_a);
+    (share_a, 1.0 - share_a)
+}
+
+#[allow(clippy::type_complexity)]
+pub fn apply_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut health_query: Query<(&mut Health, Option<&Armor>, Option<&Shield>), Without<Invulnerable>>,
+) {
+    for event in damage_events.read() {
+        if let Ok((mut health, armor, shield)) = health_query.get_mut(event.target) {
+            let absorbed = armor.map_or(0.0, |armor| armor.0.clamp(0.0, 1.0));
+            // the shield takes its share first and the armor soaks up part of what gets through
+            let shielded = shield.map_or(0.0, |shield| shield.absorb.clamp(0.0, 1.0));
+            health.value -= event.amount * (1.0 - shielded) * (1.0 - absorbed);
+        }
+    }
+}
+
+pub fn apply_status_effects(
+    mut damage_events: EventReader<DamageEvent>,
+    mut status_query: Query<&mut StatusEffects, Without<Invulnerable>>,
+) {
+    for event in damage_events.read() {
+        let Ok(mut status_effects) = status_query.get_mut(event.target) else {
+            continue;
+        };
+        for effect in event.effects.iter() {
+            status_effects.apply(*effect);
+        }
+    }
+}
+
+// counts down every active effect, burns whoever is on fire and clears out anything that wore off
+pub fn tick_status_effects(
+    mut query: Query<(Entity, &mut StatusEffects)>,
+    mut damage_events: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut status_effects) in query.iter_mut() {
+        for effect in status_effects.effects.iter_mut() {
+            let delta = time.delta_seconds().min(effect.remaining);
+            effect.remaining -= delta;
+            if let StatusEffectKind::Burn { damage_per_second } = effect.kind {
+                damage_events.send(DamageEvent {
+                    target: entity,
+                    source: entity,
+                    amount: damage_per_second * delta,
+                    direction: Vec3::ZERO,
+                    effects: Vec::new(),
+                    zone: None,
+                });
+            }
+        }
+        status_effects
+            .effects
+            .retain(|effect| effect.remaining > 0.0);
+    }
+}
+
+// counts down invulnerability and makes the entity blink while it lasts
+pub fn tick_invulnerability(
     mut commands: Commands,
-    query: Query<&mut Transform, With<Block>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+    time: Res<Time>,
+) {
+    for (entity, mut invulnerable, mut visibility) in query.iter_mut() {
+        if invulnerable.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            *visibility = Visibility::Inherited;
+        } else if (invulnerable.timer.elapsed_secs() * 5.0).fract() < 0.5 {
+            *visibility = Visibility::Inherited;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+// Puts a bubble around anything that's just been shielded, big enough to go round its collider.
+// The bubble is a child so it gets scaled along with the entity, which means the collider has to
+// be measured without its scale.
+pub fn show_shields(
+    mut commands: Commands,
+    query: Query<(Entity, Option<&Collider>), Added<Shield>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    let Ok(transform) = query.get_single() else {
+    for (entity, collider) in query.iter() {
+        let radius = collider.map_or(SHIELD_BUBBLE_RADIUS, |collider| {
+            let half_extents = Vec3::from(collider.raw.compute_local_aabb().half_extents());
+            (half_extents / collider.scale()).length() + SHIELD_BUBBLE_MARGIN
+        });
+        let bubble = PbrBundle {
+            mesh: meshes.add(Sphere::new(radius)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgba(0.3, 0.6, 1.0, 0.3),
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            }),
+            ..default()
+        };
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((bubble, ShieldBubble, Name::new("Shield")));
+        });
+    }
+}
+
+pub fn tick_shields(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Shield, Option<&Children>)>,
+    bubble_query: Query<(), With<ShieldBubble>>,
+    time: Res<Time>,
+) {
+    for (entity, mut shield, children) in query.iter_mut() {
+        if !shield.timer.tick(time.delta()).finished() {
+            continue;
+        }
+        commands.entity(entity).remove::<Shield>();
+        for child in children.into_iter().flatten() {
+            if bubble_query.contains(*child) {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+    }
+}
+
+// Knockback is proportional to the damage dealt and inversely proportional to the target's mass.
+// Entities driven by rapier (anything with an ExternalImpulse) get a real impulse, everything else
+// moves with our own kinematic model so we change its Velocity directly.
+pub fn apply_knockback(
+    mut damage_events: EventReader<DamageEvent>,
+    mut kinematic_query: Query<(&mut Velocity, Option<&Mass>), Without<ExternalImpulse>>,
+    mut rapier_query: Query<&mut ExternalImpulse>,
+) {
+    for event in damage_events.read() {
+        let impulse =
+            event.direction.with_y(0.0).normalize_or_zero() * event.amount * KNOCKBACK_PER_DAMAGE;
+
+        if let Ok(mut external_impulse) = rapier_query.get_mut(event.target) {
+            external_impulse.impulse += impulse;
+        } else if let Ok((mut velocity, mass)) = kinematic_query.get_mut(event.target) {
+            let mass = mass.map_or(1.0, |mass| mass.value.max(f32::EPSILON));
+            velocity.value += impulse / mass;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn block_weapons(
+    mut prefabs: PrefabSpawner,
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut Weapons,
+            Option<&Team>,
+            Option<&StatusEffects>,
+        ),
+        With<Block>,
+    >,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut stats: ResMut<MatchStats>,
+) {
+    let Ok((entity, transform, mut weapons, team, status_effects)) = query.get_single_mut() else {
         return;
     };
-    if keyboard_input.pressed(KeyCode::KeyF) {
-        commands.spawn((
-            MovingObjBundle {
-                velocity: Velocity::new(-transform.forward() * 25.0),
-                acceleration: Acceleration::new(Vec3::ZERO),
-                collider: Collider::cuboid(0.05, 0.05, 0.05),
-                health: Health::new(1.0),
-                collision: CollisionDamage(25.0),
+    // stunned or EMP'd robots can't shoot
+    if status_effects.is_some_and(|status_effects| status_effects.weapons_disabled()) {
+        return;
+    }
+    // the block shoots out of its front, which is +z
+    let position = transform.translation;
+    let direction = -transform.forward().as_vec3();
+    let triggers = [
+        keyboard_input.pressed(KeyCode::KeyF),
+        // the shock round does barely any damage but slows the target down and knocks out its weapons
+        keyboard_input.just_pressed(KeyCode::KeyG),
+        // rockets blow up whatever's crowded around where they land, good against swarms
+        keyboard_input.just_pressed(KeyCode::KeyH),
+    ];
+    for (weapon, pulled) in weapons.0.iter_mut().zip(triggers) {
+        if !pulled {
+            continue;
+        }
+        let fired = fire_weapon(
+            &mut prefabs,
+            &mut noise_events,
+            weapon,
+            entity,
+            position,
+            direction,
+            team.copied(),
+        );
+        if fired {
+            stats.shots_fired += 1;
+        }
+    }
+}
+
+pub fn register_projectile_prefabs(mut registry: ResMut<PrefabRegistry>) {
+    registry.register(
+        "bullet",
+        Prefab {
+            name: "bullet".to_string(),
+            role: PrefabRole::Projectile,
+            model: PrefabModel::Cuboid {
+                size: Vec3::splat(0.1),
+                colour: Color::BLACK,
             },
-            PbrBundle {
-                mesh: meshes.add(Cuboid::new(0.1, 0.1, 0.1)),
-                material: materials.add(Color::BLACK),
-                transform: Transform::from_translation(
-                    transform.translation + -transform.forward() * 1.0,
-                ),
-                ..default()
+            collider: PrefabCollider::Cuboid {
+                half_size: Vec3::splat(0.05),
+            },
+            sensor: true,
+            health: 1.0,
+            collision_damage: 25.0,
+            mass: 0.1,
+            velocity: Vec3::new(0.0, 0.0, 25.0),
+            ..default()
+        },
+    );
+    registry.register(
+        "shock_round",
+        Prefab {
+            name: "shock round".to_string(),
+            role: PrefabRole::Projectile,
+            model: PrefabModel::Sphere {
+                radius: 0.1,
+                colour: Color::srgb(0.2, 0.6, 1.0),
+            },
+            collider: PrefabCollider::Ball { radius: 0.1 },
+            sensor: true,
+            health: 1.0,
+            collision_damage: 5.0,
+            mass: 0.1,
+            velocity: Vec3::new(0.0, 0.0, 15.0),
+            on_hit: vec![
+                StatusEffect::new(StatusEffectKind::Slow { factor: 0.5 }, 3.0),
+                StatusEffect::new(StatusEffectKind::Emp, 3.0),
+            ],
+            ..default()
+        },
+    );
+    registry.register(
+        "rocket",
+        Prefab {
+            name: "rocket".to_string(),
+            role: PrefabRole::Projectile,
+            model: PrefabModel::Cuboid {
+                size: Vec3::new(0.15, 0.15, 0.4),
+                colour: Color::srgb(0.6, 0.1, 0.1),
             },
-            Bullet,
-            Sensor,
-            Name::new("bullet"),
-        ));
+            collider: PrefabCollider::Cuboid {
+                half_size: Vec3::new(0.075, 0.075, 0.2),
+            },
+            sensor: true,
+            health: 1.0,
+            collision_damage: 20.0,
+            mass: 0.5,
+            velocity: Vec3::new(0.0, 0.0, 18.0),
+            blast: Some(Blast {
+                radius: 3.0,
+                damage: 60.0,
+            }),
+            ..default()
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ram_shares_add_up_to_one() {
+        for (mass_a, mass_b, speed_a, speed_b) in [
+            (1.0, 1.0, 0.0, 0.0),
+            (1.0, 4.0, 3.0, 0.0),
+            (10.0, 0.5, 0.2, 8.0),
+        ] {
+            let (share_a, share_b) = ram_shares(mass_a, mass_b, speed_a, speed_b);
+            assert!((share_a + share_b - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn lighter_robot_takes_more_of_a_ram() {
+        let (light, heavy) = ram_shares(1.0, 4.0, 2.0, 2.0);
+        assert!(light > heavy);
+    }
+
+    #[test]
+    fn slower_robot_takes_more_of_a_ram() {
+        let (slow, fast) = ram_shares(2.0, 2.0, 1.0, 5.0);
+        assert!(slow > fast);
     }
 }
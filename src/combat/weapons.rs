@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::prefabs::{PrefabOverrides, PrefabSpawner};
+
+use super::*;
+
+// how far in front of the shooter a projectile appears, so it doesn't start inside the shooter
+const MUZZLE_DISTANCE: f32 = 1.0;
+// how far away a shot can be heard
+const SHOT_NOISE_RADIUS: f32 = 15.0;
+
+pub fn tick_weapon_cooldowns(mut query: Query<&mut Weapons>, time: Res<Time>) {
+    for mut weapons in query.iter_mut() {
+        for weapon in weapons.0.iter_mut() {
+            weapon.cooldown = (weapon.cooldown - time.delta_seconds()).max(0.0);
+        }
+    }
+}
+
+// Fires weapon from position towards direction if it has cooled down, with a random amount of
+// spread. The projectile is put on the shooter's team so it can't hurt its own side, and the bang
+// goes out as a NoiseEvent. Returns whether a shot was fired.
+pub fn fire_weapon(
+    prefabs: &mut PrefabSpawner,
+    noise_events: &mut EventWriter<NoiseEvent>,
+    weapon: &mut Weapon,
+    shooter: Entity,
+    position: Vec3,
+    direction: Vec3,
+    team: Option<Team>,
+) -> bool {
+    if weapon.cooldown > 0.0 {
+        return false;
+    }
+    let direction = Vec3::new(direction.x, 0.0, direction.z).normalize_or_zero();
+    if direction == Vec3::ZERO {
+        return false;
+    }
+    weapon.cooldown = 1.0 / weapon.fire_rate.max(f32::EPSILON);
+
+    // a negative spread in an enemy file means the same as a positive one
+    let spread = weapon.spread.abs();
+    let spread = rand::thread_rng().gen_range(-spread..=spread);
+    let direction = Quat::from_rotation_y(spread) * direction;
+    // projectiles fly out of their +z, which is the opposite of bevy's forward
+    let overrides = PrefabOverrides {
+        rotation: Transform::default()
+            .looking_to(-direction, Vec3::Y)
+            .rotation,
+        team,
+        ..PrefabOverrides::at(position + direction * MUZZLE_DISTANCE)
+    };
+    if prefabs.spawn(&weapon.projectile, overrides).is_none() {
+        return false;
+    }
+    noise_events.send(NoiseEvent {
+        source: shooter,
+        position,
+        radius: SHOT_NOISE_RADIUS,
+        team,
+    });
+    true
+}
+
+// Where to aim so a projectile moving at projectile_speed meets a target moving at target_velocity.
+// Falls back to aiming straight at the target when it's too quick to ever catch.
+pub fn lead_target(
+    position: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    projectile_speed: f32,
+) -> Vec3 {
+    let offset = target - position;
+    // solve |offset + target_velocity * t| = projectile_speed * t for the time t the shot lands
+    let a = target_velocity.length_squared() - projectile_speed * projectile_speed;
+    let b = 2.0 * offset.dot(target_velocity);
+    let c = offset.length_squared();
+    let time = if a.abs() < f32::EPSILON {
+        (b < 0.0).then(|| -c / b)
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            None
+        } else {
+            let root = discriminant.sqrt();
+            [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+                .into_iter()
+                .filter(|time| *time > 0.0)
+                .min_by(|first, second| first.total_cmp(second))
+        }
+    };
+    match time {
+        Some(time) => offset + target_velocity * time,
+        None => offset,
+    }
+}
@@ -0,0 +1,120 @@
+// A small debug console for spawning prefabs while playing, it only shows up with dev tools on.
+//   spawn <name> [x y z] [player|enemy]   spawns a prefab or enemy archetype
+//   list                                  lists everything that can be spawned
+//   dump <name>                           prints a prefab the way a .prefab.ron file would hold it
+use crate::{
+    combat::Team,
+    prefabs::{prefab_to_ron, PrefabOverrides, PrefabRegistry, PrefabSpawner},
+    schedule::InGameSet,
+    settings::dev_tools_enabled,
+};
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_inspector_egui::bevy_egui::EguiContext;
+
+const MAX_CONSOLE_LINES: usize = 100;
+
+#[derive(Resource, Debug, Default)]
+pub struct Console {
+    pub input: String,
+    pub output: Vec<String>,
+}
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>().add_systems(
+            Update,
+            console_ui
+                .run_if(dev_tools_enabled)
+                .in_set(InGameSet::UserInput),
+        );
+    }
+}
+
+fn console_ui(
+    mut primary_window: Query<&mut EguiContext, With<PrimaryWindow>>,
+    mut console: ResMut<Console>,
+    mut prefabs: PrefabSpawner,
+    registry: Res<PrefabRegistry>,
+    type_registry: Res<AppTypeRegistry>,
+) {
+    let mut submitted = None;
+    for mut context in primary_window.iter_mut() {
+        egui::Window::new("Console").show(context.get_mut(), |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for line in console.output.iter() {
+                        ui.monospace(line);
+                    }
+                });
+            let response = ui.text_edit_singleline(&mut console.input);
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                submitted = Some(std::mem::take(&mut console.input));
+                response.request_focus();
+            }
+        });
+    }
+
+    let Some(line) = submitted else {
+        return;
+    };
+    console.output.push(format!("> {line}"));
+    let reply = run_command(&line, &mut prefabs, &registry, &type_registry);
+    console.output.extend(reply.lines().map(str::to_string));
+    let overflow = console.output.len().saturating_sub(MAX_CONSOLE_LINES);
+    console.output.drain(..overflow);
+}
+
+fn run_command(
+    line: &str,
+    prefabs: &mut PrefabSpawner,
+    registry: &PrefabRegistry,
+    type_registry: &AppTypeRegistry,
+) -> String {
+    let mut words = line.split_whitespace();
+    match words.next() {
+        Some("spawn") => {
+            let Some(name) = words.next() else {
+                return "usage: spawn <name> [x y z] [player|enemy]".to_string();
+            };
+            let rest: Vec<&str> = words.collect();
+            let (coords, team) = match rest.last() {
+                Some(&"player") => (&rest[..rest.len() - 1], Some(Team::Player)),
+                Some(&"enemy") => (&rest[..rest.len() - 1], Some(Team::Enemy)),
+                _ => (&rest[..], None),
+            };
+            let translation = match coords {
+                [] => Vec3::new(0.0, 0.5, 0.0),
+                [x, y, z] => match (x.parse(), y.parse(), z.parse()) {
+                    (Ok(x), Ok(y), Ok(z)) => Vec3::new(x, y, z),
+                    _ => return format!("bad position: {}", coords.join(" ")),
+                },
+                _ => return "position needs x, y and z".to_string(),
+            };
+            let overrides = PrefabOverrides {
+                team,
+                ..PrefabOverrides::at(translation)
+            };
+            match prefabs.spawn(name, overrides) {
+                Some(entity) => format!("spawned {name} as {:?}", entity.id()),
+                None => format!("nothing called {name}, try list"),
+            }
+        }
+        Some("list") => prefabs.ids().join("\n"),
+        Some("dump") => {
+            let Some(name) = words.next() else {
+                return "usage: dump <name>".to_string();
+            };
+            match registry.get(name) {
+                Some(prefab) => prefab_to_ron(prefab, type_registry)
+                    .unwrap_or_else(|error| format!("could not write {name}: {error}")),
+                None => format!("no prefab called {name}"),
+            }
+        }
+        Some(command) => format!("unknown command {command}, try spawn, list or dump"),
+        None => String::new(),
+    }
+}
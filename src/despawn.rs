@@ -1,4 +1,10 @@
-use crate::{combat::Health, schedule::InGameSet, states::GameState, entities::Bullet};
+use crate::{
+    combat::Health,
+    entities::{Block, Bullet, Dummy},
+    lives::PlayerDestroyed,
+    schedule::InGameSet,
+    stats::MatchStats,
+};
 use bevy::prelude::*;
 
 pub struct DespawnPlugin;
@@ -7,32 +13,28 @@ impl Plugin for DespawnPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (despawn_far_away_entities, despawn_dead_entities).in_set(InGameSet::DespawnEntities),
-        )
-        .add_systems(OnEnter(GameState::GameOver), despawn_all_entities);
+            despawn_dead_entities.in_set(InGameSet::DespawnEntities),
+        );
     }
 }
 
-fn despawn_far_away_entities(mut commands: Commands, query: Query<(Entity, &GlobalTransform)>) {
-    for (entity, transform) in query.iter() {
-        let distance = transform.translation().distance(Vec3::ZERO);
-
-        if distance > 25.0 {
-            commands.entity(entity).despawn_recursive();
-        }
-    }
-}
-
-fn despawn_dead_entities(mut commands: Commands, query: Query<(Entity, &Health), Without<Bullet>>) {
-    for (entity, health) in query.iter() {
+// anything that leaves the arena is dealt with by its OutOfBounds, see arena/systems.rs
+#[allow(clippy::type_complexity)]
+fn despawn_dead_entities(
+    mut commands: Commands,
+    query: Query<(Entity, &Health, Has<Block>, Has<Dummy>), Without<Bullet>>,
+    mut destroyed_events: EventWriter<PlayerDestroyed>,
+    mut stats: ResMut<MatchStats>,
+) {
+    for (entity, health, is_block, is_dummy) in query.iter() {
         if health.value <= 0.0 {
             commands.entity(entity).despawn_recursive();
+            if is_block {
+                destroyed_events.send(PlayerDestroyed);
+            }
+            if is_dummy {
+                stats.enemies_destroyed += 1;
+            }
         }
     }
 }
-
-fn despawn_all_entities(mut commands: Commands, query: Query<Entity, With<Health>>) {
-    for entity in query.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
-}
@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+
+// a patch of floor that hurts anything over it, see HazardDefinition::DamageZone
+#[derive(Component, Debug)]
+pub struct DamageZone {
+    pub half_size: Vec2,
+    pub damage_per_second: f32,
+}
+
+// a hole in the floor, anything with health whose middle ends up over it is gone
+#[derive(Component, Debug)]
+pub struct Pit {
+    pub half_size: Vec2,
+}
+
+// Spins the blade round. The damage comes from the saw's CollisionDamage, the same as anything else
+// that hurts on contact.
+#[derive(Component, Debug)]
+pub struct SawBlade {
+    pub spin_speed: f32, // radians per second
+}
+
+// hits everything within radius each time the timer goes off
+#[derive(Component, Debug)]
+pub struct Hammer {
+    pub radius: f32,
+    pub damage: f32,
+    pub stun: f32,
+    pub timer: Timer,
+}
+
+// the head of a hammer, it's lifted up and slammed down in time with its hammer's timer
+#[derive(Component, Debug)]
+pub struct HammerHead;
+
+// switches its Flame on for on_time seconds and off for off_time seconds, starting off
+#[derive(Component, Debug)]
+pub struct FlameJet {
+    pub on_time: f32,
+    pub off_time: f32,
+    pub lit: bool,
+    pub timer: Timer,
+}
+
+// The column of fire above a flame jet. It burns things through its CollisionDamage and
+// StatusOnHit, while the jet is off its collider is switched off so it can't touch anything.
+#[derive(Component, Debug)]
+pub struct Flame;
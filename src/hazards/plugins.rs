@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+use crate::schedule::InGameSet;
+
+use super::systems::*;
+
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        // the hazards are spawned along with the rest of the arena in build_arena
+        app.add_systems(
+            Update,
+            (
+                apply_damage_zones,
+                drop_into_pits,
+                spin_saws,
+                swing_hammers,
+                cycle_flame_jets,
+            )
+                .in_set(InGameSet::EntityUpdates),
+        );
+    }
+}
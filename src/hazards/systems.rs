@@ -0,0 +1,380 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_rapier3d::prelude::{Collider, ColliderDisabled, Sensor};
+
+use crate::{
+    arena::HazardDefinition,
+    combat::{CollisionDamage, DamageEvent, Health, StatusEffect, StatusEffectKind, StatusOnHit},
+    navigation::Obstacle,
+    spatial::SpatialHash,
+};
+
+use super::*;
+
+// patches on the floor sit just above it so they don't flicker
+const FLOOR_MARKING_HEIGHT: f32 = 0.01;
+// Pits have a collider this tall so the navigation grid steers around them. It stays under the
+// height robots look at each other from, so it doesn't get in the way of line of sight.
+const PIT_COLLIDER_HEIGHT: f32 = 0.4;
+const SAW_THICKNESS: f32 = 0.1;
+const SAW_SPIN_SPEED: f32 = 20.0;
+// how high a hammer's head is lifted between strikes
+const HAMMER_RAISED_HEIGHT: f32 = 3.0;
+// the head takes this long to come down, and the strike lands the moment it reaches the floor
+const HAMMER_DROP_TIME: f32 = 0.15;
+const HAMMER_RISE_TIME: f32 = 1.0;
+const HAMMER_HEAD_THICKNESS: f32 = 0.6;
+const NOZZLE_HEIGHT: f32 = 0.1;
+
+// Spawns the pieces of one hazard. The caller gets the hazard's root entity back to add anything
+// else it needs, build_arena makes it part of the arena.
+pub fn spawn_hazard<'a>(
+    commands: &'a mut Commands,
+    hazard: &HazardDefinition,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) -> EntityCommands<'a> {
+    match *hazard {
+        HazardDefinition::DamageZone {
+            centre,
+            half_size,
+            damage_per_second,
+        } => {
+            let half_size = Vec2::from(half_size);
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Plane3d::new(Vec3::Y, half_size)),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::srgba(0.2, 0.9, 0.1, 0.6),
+                        alpha_mode: AlphaMode::Blend,
+                        ..default()
+                    }),
+                    transform: Transform::from_translation(
+                        Vec3::from(centre).with_y(FLOOR_MARKING_HEIGHT),
+                    ),
+                    ..default()
+                },
+                DamageZone {
+                    half_size,
+                    damage_per_second,
+                },
+                Name::new("Damage Zone"),
+            ))
+        }
+        HazardDefinition::Pit { centre, half_size } => {
+            let half_size = Vec2::from(half_size);
+            let mut pit = commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Plane3d::new(Vec3::Y, half_size)),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::BLACK,
+                        unlit: true,
+                        ..default()
+                    }),
+                    transform: Transform::from_translation(
+                        Vec3::from(centre).with_y(FLOOR_MARKING_HEIGHT),
+                    ),
+                    ..default()
+                },
+                Pit { half_size },
+                Name::new("Pit"),
+            ));
+            pit.with_children(|parent| {
+                parent.spawn((
+                    TransformBundle::from_transform(Transform::from_xyz(
+                        0.0,
+                        PIT_COLLIDER_HEIGHT * 0.5,
+                        0.0,
+                    )),
+                    Collider::cuboid(half_size.x, PIT_COLLIDER_HEIGHT * 0.5, half_size.y),
+                    Sensor,
+                    Obstacle,
+                ));
+            });
+            pit
+        }
+        HazardDefinition::Saw {
+            centre,
+            radius,
+            damage,
+            rotation,
+        } => {
+            // a cylinder stands on its end, turning it on its side makes it a blade running along
+            // z that spins around x
+            let transform = Transform::from_translation(Vec3::from(centre)).with_rotation(
+                Quat::from_rotation_y(rotation.to_radians()) * Quat::from_rotation_z(FRAC_PI_2),
+            );
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cylinder::new(radius, SAW_THICKNESS)),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::srgb(0.75, 0.75, 0.8),
+                        metallic: 0.9,
+                        perceptual_roughness: 0.3,
+                        ..default()
+                    }),
+                    transform,
+                    ..default()
+                },
+                Collider::cylinder(SAW_THICKNESS * 0.5, radius),
+                Sensor,
+                CollisionDamage(damage),
+                SawBlade {
+                    spin_speed: SAW_SPIN_SPEED,
+                },
+                Obstacle,
+                Name::new("Saw"),
+            ))
+        }
+        HazardDefinition::Hammer {
+            centre,
+            radius,
+            damage,
+            interval,
+            stun,
+        } => {
+            let mut hammer = commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Circle::new(radius)),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::srgba(0.9, 0.2, 0.1, 0.35),
+                        alpha_mode: AlphaMode::Blend,
+                        ..default()
+                    }),
+                    transform: Transform::from_translation(
+                        Vec3::from(centre).with_y(FLOOR_MARKING_HEIGHT),
+                    )
+                    .with_rotation(Quat::from_rotation_x(-FRAC_PI_2)),
+                    ..default()
+                },
+                Hammer {
+                    radius,
+                    damage,
+                    stun,
+                    // a negative time in an arena file counts as no time at all
+                    timer: Timer::from_seconds(interval.max(0.0), TimerMode::Repeating),
+                },
+                Name::new("Hammer"),
+            ));
+            hammer.with_children(|parent| {
+                // the marking is turned to lie flat, so the head's up is the marking's z
+                parent.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(Cuboid::new(
+                            radius * 1.4,
+                            radius * 1.4,
+                            HAMMER_HEAD_THICKNESS,
+                        )),
+                        material: materials.add(Color::srgb(0.3, 0.3, 0.35)),
+                        transform: Transform::from_xyz(0.0, 0.0, HAMMER_RAISED_HEIGHT),
+                        ..default()
+                    },
+                    HammerHead,
+                    Name::new("Hammer Head"),
+                ));
+            });
+            hammer
+        }
+        HazardDefinition::FlameJet {
+            centre,
+            radius,
+            height,
+            on_time,
+            off_time,
+            damage,
+            burn_damage_per_second,
+            burn_duration,
+        } => {
+            let (on_time, off_time) = (on_time.max(0.0), off_time.max(0.0));
+            let mut jet = commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cylinder::new(radius * 0.5, NOZZLE_HEIGHT)),
+                    material: materials.add(Color::srgb(0.15, 0.15, 0.15)),
+                    transform: Transform::from_translation(
+                        Vec3::from(centre) + Vec3::Y * NOZZLE_HEIGHT * 0.5,
+                    ),
+                    ..default()
+                },
+                FlameJet {
+                    on_time,
+                    off_time,
+                    lit: false,
+                    timer: Timer::from_seconds(off_time, TimerMode::Once),
+                },
+                Name::new("Flame Jet"),
+            ));
+            jet.with_children(|parent| {
+                parent.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(Cylinder::new(radius, height)),
+                        material: materials.add(StandardMaterial {
+                            base_color: Color::srgba(1.0, 0.45, 0.05, 0.7),
+                            emissive: LinearRgba::rgb(4.0, 1.2, 0.1),
+                            alpha_mode: AlphaMode::Blend,
+                            ..default()
+                        }),
+                        transform: Transform::from_xyz(0.0, height * 0.5, 0.0),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    Collider::cylinder(height * 0.5, radius),
+                    Sensor,
+                    ColliderDisabled,
+                    CollisionDamage(damage),
+                    StatusOnHit(vec![StatusEffect::new(
+                        StatusEffectKind::Burn {
+                            damage_per_second: burn_damage_per_second,
+                        },
+                        burn_duration,
+                    )]),
+                    Flame,
+                    Name::new("Flame"),
+                ));
+            });
+            jet
+        }
+    }
+}
+
+// Hurts everything standing in a damage zone a little every frame
+pub fn apply_damage_zones(
+    zone_query: Query<(Entity, &GlobalTransform, &DamageZone)>,
+    health_query: Query<(), With<Health>>,
+    spatial_hash: Res<SpatialHash>,
+    mut damage_events: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    for (zone_entity, zone_transform, zone) in zone_query.iter() {
+        let centre = zone_transform.translation();
+        for (target, position) in spatial_hash.within(centre, zone.half_size.length()) {
+            let offset = (position - centre).abs();
+            if offset.x > zone.half_size.x || offset.z > zone.half_size.y {
+                continue;
+            }
+            if !health_query.contains(target) {
+                continue;
+            }
+            damage_events.send(DamageEvent {
+                target,
+                source: zone_entity,
+                amount: zone.damage_per_second * time.delta_seconds(),
+                direction: Vec3::ZERO, // no knockback, it's the floor
+                effects: Vec::new(),
+                zone: None,
+            });
+        }
+    }
+}
+
+// Anything with health whose middle is over a pit has fallen in. It dies like it would from
+// damage, so it still counts and drops loot.
+pub fn drop_into_pits(
+    pit_query: Query<(&GlobalTransform, &Pit)>,
+    mut health_query: Query<&mut Health>,
+    spatial_hash: Res<SpatialHash>,
+) {
+    for (pit_transform, pit) in pit_query.iter() {
+        let centre = pit_transform.translation();
+        for (entity, position) in spatial_hash.within(centre, pit.half_size.length()) {
+            let offset = (position - centre).abs();
+            if offset.x > pit.half_size.x || offset.z > pit.half_size.y {
+                continue;
+            }
+            if let Ok(mut health) = health_query.get_mut(entity) {
+                if health.value > 0.0 {
+                    info!("{entity:?} fell into a pit");
+                    health.value = 0.0;
+                }
+            }
+        }
+    }
+}
+
+pub fn spin_saws(mut query: Query<(&SawBlade, &mut Transform)>, time: Res<Time>) {
+    for (saw, mut transform) in query.iter_mut() {
+        transform.rotate_local_y(saw.spin_speed * time.delta_seconds());
+    }
+}
+
+// Lifts each hammer's head, drops it just before the timer runs out and hits everything under it
+// the moment it lands. Whatever it hits is knocked away from the middle and maybe stunned too.
+pub fn swing_hammers(
+    mut hammer_query: Query<(Entity, &GlobalTransform, &mut Hammer, &Children)>,
+    mut head_query: Query<&mut Transform, With<HammerHead>>,
+    health_query: Query<(), With<Health>>,
+    spatial_hash: Res<SpatialHash>,
+    mut damage_events: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    for (entity, transform, mut hammer, children) in hammer_query.iter_mut() {
+        hammer.timer.tick(time.delta());
+
+        let since_strike = hammer.timer.elapsed_secs();
+        let until_strike = hammer.timer.remaining_secs();
+        let height = if until_strike < HAMMER_DROP_TIME {
+            HAMMER_RAISED_HEIGHT * until_strike / HAMMER_DROP_TIME
+        } else {
+            HAMMER_RAISED_HEIGHT * (since_strike / HAMMER_RISE_TIME).min(1.0)
+        };
+        for child in children.iter() {
+            if let Ok(mut head) = head_query.get_mut(*child) {
+                head.translation.z = height + HAMMER_HEAD_THICKNESS * 0.5;
+            }
+        }
+
+        if !hammer.timer.just_finished() {
+            continue;
+        }
+        let centre = transform.translation();
+        let effects = if hammer.stun > 0.0 {
+            vec![StatusEffect::new(StatusEffectKind::Stun, hammer.stun)]
+        } else {
+            Vec::new()
+        };
+        for (target, position) in spatial_hash.within(centre, hammer.radius) {
+            if !health_query.contains(target) {
+                continue;
+            }
+            damage_events.send(DamageEvent {
+                target,
+                source: entity,
+                amount: hammer.damage,
+                direction: position - centre,
+                effects: effects.clone(),
+                zone: None,
+            });
+        }
+    }
+}
+
+// Turns each flame jet's fire on and off. While it's off the fire is hidden and its collider is
+// switched off, so its CollisionDamage can't reach anything.
+pub fn cycle_flame_jets(
+    mut commands: Commands,
+    mut jet_query: Query<(&mut FlameJet, &Children)>,
+    mut flame_query: Query<&mut Visibility, With<Flame>>,
+    time: Res<Time>,
+) {
+    for (mut jet, children) in jet_query.iter_mut() {
+        if !jet.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        jet.lit = !jet.lit;
+        let duration = if jet.lit { jet.on_time } else { jet.off_time };
+        jet.timer = Timer::from_seconds(duration, TimerMode::Once);
+
+        for child in children.iter() {
+            let Ok(mut visibility) = flame_query.get_mut(*child) else {
+                continue;
+            };
+            if jet.lit {
+                *visibility = Visibility::Inherited;
+                commands.entity(*child).remove::<ColliderDisabled>();
+            } else {
+                *visibility = Visibility::Hidden;
+                commands.entity(*child).insert(ColliderDisabled);
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    arena::CurrentArena,
+    block::spawn_block,
+    combat::Invulnerable,
+    prefabs::PrefabSpawner,
+    schedule::InGameSet,
+    states::{GameFlowEvent, GameState},
+};
+use bevy::prelude::*;
+use bevy_rapier3d::{
+    plugin::RapierContext,
+    prelude::{Collider, QueryFilter},
+};
+
+const STARTING_LIVES: u32 = 3;
+const RESPAWN_DELAY: f32 = 3.0; // seconds between losing the block and getting it back
+const SPAWN_INVULNERABILITY: f32 = 2.0;
+const SAFE_SPAWN_RADIUS: f32 = 1.5; // nothing can be inside this radius of a respawn point
+
+#[derive(Resource, Debug)]
+pub struct Lives {
+    pub remaining: u32, // spare lives, the game ends when the block is destroyed with none left
+}
+
+impl Default for Lives {
+    fn default() -> Self {
+        Self {
+            remaining: STARTING_LIVES,
+        }
+    }
+}
+
+// Counts down to the block coming back, None while the block is alive
+#[derive(Resource, Debug, Default)]
+pub struct RespawnCountdown {
+    pub timer: Option<Timer>,
+}
+
+// Sent whenever the player's block is destroyed, whatever destroyed it
+#[derive(Event, Debug)]
+pub struct PlayerDestroyed;
+
+pub struct LivesPlugin;
+
+impl Plugin for LivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Lives>()
+            .init_resource::<RespawnCountdown>()
+            .add_event::<PlayerDestroyed>()
+            .add_systems(OnExit(GameState::Loading), reset_lives)
+            .add_systems(
+                Update,
+                (handle_player_destroyed, tick_respawn_countdown)
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
+            );
+    }
+}
+
+fn reset_lives(mut lives: ResMut<Lives>, mut countdown: ResMut<RespawnCountdown>) {
+    *lives = Lives::default();
+    *countdown = RespawnCountdown::default();
+}
+
+fn handle_player_destroyed(
+    mut destroyed_events: EventReader<PlayerDestroyed>,
+    mut lives: ResMut<Lives>,
+    mut countdown: ResMut<RespawnCountdown>,
+    mut flow_events: EventWriter<GameFlowEvent>,
+) {
+    for _ in destroyed_events.read() {
+        if countdown.timer.is_some() {
+            continue;
+        }
+        if lives.remaining == 0 {
+            flow_events.send(GameFlowEvent::PlayerDefeated);
+            continue;
+        }
+        lives.remaining -= 1;
+        countdown.timer = Some(Timer::from_seconds(RESPAWN_DELAY, TimerMode::Once));
+    }
+}
+
+fn tick_respawn_countdown(
+    mut prefabs: PrefabSpawner,
+    mut countdown: ResMut<RespawnCountdown>,
+    arena: Res<CurrentArena>,
+    rapier_context: Res<RapierContext>,
+    time: Res<Time>,
+) {
+    let Some(timer) = countdown.timer.as_mut() else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+    countdown.timer = None;
+
+    // the arena's player spawns are tried in order, the first clear one wins
+    let position = safe_respawn_point(&arena.0.player_spawns(), &rapier_context)
+        .unwrap_or(arena.0.player_spawn());
+    if let Some(mut block) = spawn_block(&mut prefabs, position) {
+        block.insert(Invulnerable::new(SPAWN_INVULNERABILITY));
+    }
+}
+
+// the first respawn point with nothing overlapping it
+fn safe_respawn_point(points: &[Vec3], rapier_context: &RapierContext) -> Option<Vec3> {
+    let probe = Collider::ball(SAFE_SPAWN_RADIUS);
+    points.iter().copied().find(|point| {
+        let mut occupied = false;
+        rapier_context.intersections_with_shape(
+            *point,
+            Quat::IDENTITY,
+            &probe,
+            QueryFilter::default(),
+            |_| {
+                occupied = true;
+                false // stop at the first thing we find
+            },
+        );
+        !occupied
+    })
+}
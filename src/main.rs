@@ -1,15 +1,36 @@
+pub mod ai;
+pub mod archetypes;
+pub mod arena;
+pub mod asset_loader;
 pub mod block;
+pub mod boss;
 pub mod combat;
+pub mod console;
 pub mod debug;
 pub mod despawn;
 pub mod entities;
+pub mod hazards;
+pub mod lives;
+pub mod mechanisms;
 pub mod movement;
+pub mod navigation;
+pub mod pickups;
+pub mod prefabs;
+pub mod round;
+pub mod scene_collider;
 pub mod schedule;
+pub mod settings;
 pub mod setup;
+pub mod spatial;
 pub mod states;
+pub mod stats;
 pub mod ui;
-pub mod asset_loader;
+pub mod waves;
 
+use ai::AiPlugin;
+use archetypes::ArchetypePlugin;
+use arena::ArenaPlugin;
+use asset_loader::AssetLoaderPlugin;
 use bevy::prelude::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_rapier3d::{
@@ -17,17 +38,34 @@ use bevy_rapier3d::{
     render::RapierDebugRenderPlugin,
 };
 use block::BlockPlugin;
+use boss::BossPlugin;
 use combat::CombatManagementPlugin;
+use console::ConsolePlugin;
 use despawn::DespawnPlugin;
+use hazards::HazardPlugin;
+use lives::LivesPlugin;
+use mechanisms::MechanismPlugin;
 use movement::MovementPlugin;
+use navigation::NavigationPlugin;
+use pickups::PickupPlugin;
+use prefabs::PrefabPlugin;
+use round::RoundPlugin;
+use scene_collider::SceneColliderPlugin;
+use schedule::SchedulePlugin;
+use settings::{inspector_enabled, SettingsPlugin};
 use setup::StartingWorldPlugin;
+use spatial::SpatialHashPlugin;
 use states::StatePlugin;
+use stats::StatsPlugin;
 use ui::UIPlugin;
-use asset_loader::AssetLoaderPlugin;
+use waves::WavePlugin;
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
         .add_plugins(AssetLoaderPlugin)
+        .add_plugins(ArchetypePlugin)
+        .add_plugins(PrefabPlugin)
+        .add_plugins(SceneColliderPlugin)
         // physics
         .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
         .add_plugins(RapierDebugRenderPlugin::default())
@@ -35,12 +73,27 @@ fn main() {
         .add_plugins(CombatManagementPlugin)
         //.add_plugins(DebugPlugin)
         .add_plugins(MovementPlugin)
+        .add_plugins(SpatialHashPlugin)
+        .add_plugins(NavigationPlugin)
+        .add_plugins(AiPlugin)
+        .add_plugins(BossPlugin)
         .add_plugins(BlockPlugin)
-        .add_plugins(WorldInspectorPlugin::default())
+        .add_plugins(WorldInspectorPlugin::default().run_if(inspector_enabled))
+        .add_plugins(SettingsPlugin)
         .add_plugins(StartingWorldPlugin)
+        .add_plugins(ArenaPlugin)
+        .add_plugins(HazardPlugin)
+        .add_plugins(MechanismPlugin)
         // .add_plugins(CollisionDetectionPlugin)
         .add_plugins(DespawnPlugin)
         .add_plugins(UIPlugin)
         .add_plugins(StatePlugin)
+        .add_plugins(StatsPlugin)
+        .add_plugins(RoundPlugin)
+        .add_plugins(LivesPlugin)
+        .add_plugins(WavePlugin)
+        .add_plugins(PickupPlugin)
+        .add_plugins(ConsolePlugin)
+        .add_plugins(SchedulePlugin)
         .run();
 }
@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+
+// Travels along a path at speed, see MechanismDefinition for how paths work. Platforms and pushers
+// both use it.
+#[derive(Component, Debug)]
+pub struct PathFollower {
+    pub points: Vec<Vec3>,
+    pub speed: f32,
+    pub looped: bool,
+    pub travelled: f32, // how far it's gone since it started
+    pub velocity: Vec3, // how fast it moved this frame, used to carry things along with it
+}
+
+impl PathFollower {
+    pub fn new(points: Vec<Vec3>, speed: f32, looped: bool) -> Self {
+        Self {
+            points,
+            speed,
+            looped,
+            travelled: 0.0,
+            velocity: Vec3::ZERO,
+        }
+    }
+
+    // where it is once it's travelled distance along the path
+    pub fn position_at(&self, distance: f32) -> Vec3 {
+        let Some(first) = self.points.first().copied() else {
+            return Vec3::ZERO;
+        };
+        let mut route = self.points.clone();
+        if self.looped {
+            route.push(first);
+        }
+        let length: f32 = route.windows(2).map(|pair| pair[0].distance(pair[1])).sum();
+        if length <= f32::EPSILON {
+            return first;
+        }
+
+        // going there and back again takes twice as long as the path, the way back is the way
+        // there backwards
+        let mut distance = if self.looped {
+            distance.rem_euclid(length)
+        } else {
+            let distance = distance.rem_euclid(length * 2.0);
+            if distance > length {
+                length * 2.0 - distance
+            } else {
+                distance
+            }
+        };
+        for pair in route.windows(2) {
+            let segment = pair[0].distance(pair[1]);
+            if distance <= segment {
+                return pair[0].lerp(pair[1], distance / segment.max(f32::EPSILON));
+            }
+            distance -= segment;
+        }
+        route.last().copied().unwrap_or(first)
+    }
+}
+
+// A piece of floor that moves, anything whose middle is over it gets carried along. The shape is in
+// the surface's own space.
+#[derive(Component, Debug, Clone, Copy)]
+pub enum MovingSurface {
+    Rectangle { half_size: Vec2 }, // half the width (x) and depth (z)
+    Circle { radius: f32 },
+}
+
+impl MovingSurface {
+    pub fn contains(&self, local: Vec3) -> bool {
+        match *self {
+            MovingSurface::Rectangle { half_size } => {
+                local.x.abs() <= half_size.x && local.z.abs() <= half_size.y
+            }
+            MovingSurface::Circle { radius } => local.xz().length() <= radius,
+        }
+    }
+
+    // how far it reaches from its middle
+    pub fn reach(&self) -> f32 {
+        match *self {
+            MovingSurface::Rectangle { half_size } => half_size.length(),
+            MovingSurface::Circle { radius } => radius,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Turntable {
+    pub angular_speed: f32, // radians per second, anticlockwise seen from above
+}
+
+// carries everything on it towards its own +z
+#[derive(Component, Debug)]
+pub struct Conveyor {
+    pub speed: f32,
+    pub length: f32,
+}
+
+// one of the lines across a conveyor's belt, they slide along so you can see which way it's going
+#[derive(Component, Debug)]
+pub struct ConveyorSlat;
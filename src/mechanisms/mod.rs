@@ -0,0 +1,7 @@
+mod components;
+mod plugins;
+mod systems;
+
+pub use components::*;
+pub use plugins::*;
+pub use systems::spawn_mechanism;
@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+
+use crate::{movement::ApplyMovement, schedule::InGameSet};
+
+use super::systems::*;
+
+pub struct MechanismPlugin;
+
+impl Plugin for MechanismPlugin {
+    fn build(&self, app: &mut App) {
+        // the mechanisms are spawned along with the rest of the arena in build_arena. They move
+        // first and then work out who they're carrying, all before anything else moves so what's
+        // on them keeps up with them.
+        app.add_systems(
+            Update,
+            (
+                (move_along_paths, spin_turntables, carry_riders)
+                    .chain()
+                    .before(ApplyMovement),
+                scroll_conveyor_slats,
+            )
+                .in_set(InGameSet::EntityUpdates),
+        );
+    }
+}
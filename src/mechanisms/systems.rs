@@ -0,0 +1,263 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_rapier3d::prelude::Collider;
+
+use crate::{
+    arena::{MechanismDefinition, Solid},
+    entities::Bullet,
+    movement::{SurfaceVelocity, Velocity},
+    spatial::SpatialHash,
+};
+
+use super::*;
+
+// platforms, turntables and conveyors are this thick, just enough to sit on top of the floor
+const SURFACE_THICKNESS: f32 = 0.05;
+// the spatial hash is built before anything moves this frame, so look a little further out
+const HASH_SLACK: f32 = 1.0;
+const SLAT_SPACING: f32 = 1.0;
+const SLAT_WIDTH: f32 = 0.15;
+
+fn path_points(path: &[[f32; 3]], height: f32) -> Vec<Vec3> {
+    path.iter()
+        .map(|point| Vec3::from(*point) + Vec3::Y * height)
+        .collect()
+}
+
+// Spawns the pieces of one mechanism. The caller gets its root entity back to add anything else it
+// needs, build_arena makes it part of the arena.
+pub fn spawn_mechanism<'a>(
+    commands: &'a mut Commands,
+    mechanism: &MechanismDefinition,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) -> EntityCommands<'a> {
+    match mechanism {
+        MechanismDefinition::Platform {
+            size,
+            path,
+            speed,
+            looped,
+        } => {
+            let follower =
+                PathFollower::new(path_points(path, SURFACE_THICKNESS * 0.5), *speed, *looped);
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cuboid::new(size[0], SURFACE_THICKNESS, size[1])),
+                    material: materials.add(Color::srgb(0.45, 0.5, 0.6)),
+                    transform: Transform::from_translation(follower.position_at(0.0)),
+                    ..default()
+                },
+                MovingSurface::Rectangle {
+                    half_size: Vec2::from(*size) * 0.5,
+                },
+                follower,
+                Name::new("Platform"),
+            ))
+        }
+        MechanismDefinition::Turntable {
+            centre,
+            radius,
+            speed,
+        } => {
+            let mut turntable = commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cylinder::new(*radius, SURFACE_THICKNESS)),
+                    material: materials.add(Color::srgb(0.5, 0.5, 0.55)),
+                    transform: Transform::from_translation(
+                        Vec3::from(*centre) + Vec3::Y * SURFACE_THICKNESS * 0.5,
+                    ),
+                    ..default()
+                },
+                MovingSurface::Circle { radius: *radius },
+                Turntable {
+                    angular_speed: speed.to_radians(),
+                },
+                Name::new("Turntable"),
+            ));
+            // a stripe across the middle so you can see it going round
+            turntable.with_children(|parent| {
+                parent.spawn(PbrBundle {
+                    mesh: meshes.add(Cuboid::new(
+                        radius * 1.9,
+                        SURFACE_THICKNESS * 1.2,
+                        SLAT_WIDTH * 2.0,
+                    )),
+                    material: materials.add(Color::srgb(0.9, 0.75, 0.1)),
+                    ..default()
+                });
+            });
+            turntable
+        }
+        MechanismDefinition::Conveyor {
+            centre,
+            size,
+            rotation,
+            speed,
+        } => {
+            let [width, length] = *size;
+            let mut conveyor = commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cuboid::new(width, SURFACE_THICKNESS, length)),
+                    material: materials.add(Color::srgb(0.2, 0.2, 0.22)),
+                    transform: Transform::from_translation(
+                        Vec3::from(*centre) + Vec3::Y * SURFACE_THICKNESS * 0.5,
+                    )
+                    .with_rotation(Quat::from_rotation_y(rotation.to_radians())),
+                    ..default()
+                },
+                MovingSurface::Rectangle {
+                    half_size: Vec2::new(width, length) * 0.5,
+                },
+                Conveyor {
+                    speed: *speed,
+                    length,
+                },
+                Name::new("Conveyor"),
+            ));
+            let slats = (length / SLAT_SPACING).floor().max(1.0) as i32;
+            let slat_mesh = meshes.add(Cuboid::new(width, SURFACE_THICKNESS * 1.2, SLAT_WIDTH));
+            let slat_material = materials.add(Color::srgb(0.45, 0.45, 0.45));
+            conveyor.with_children(|parent| {
+                for slat in 0..slats {
+                    let along = (slat as f32 + 0.5) / slats as f32 - 0.5;
+                    parent.spawn((
+                        PbrBundle {
+                            mesh: slat_mesh.clone(),
+                            material: slat_material.clone(),
+                            transform: Transform::from_xyz(0.0, 0.0, along * length),
+                            ..default()
+                        },
+                        ConveyorSlat,
+                    ));
+                }
+            });
+            conveyor
+        }
+        MechanismDefinition::Pusher {
+            size,
+            path,
+            speed,
+            looped,
+        } => {
+            let size = Vec3::from(*size);
+            let follower = PathFollower::new(path_points(path, size.y * 0.5), *speed, *looped);
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cuboid::from_size(size)),
+                    material: materials.add(Color::srgb(0.7, 0.55, 0.1)),
+                    transform: Transform::from_translation(follower.position_at(0.0)),
+                    ..default()
+                },
+                Collider::cuboid(size.x * 0.5, size.y * 0.5, size.z * 0.5),
+                // being solid is what shoves robots, anything it moves into is pushed back out
+                Solid,
+                follower,
+                Name::new("Pusher"),
+            ))
+        }
+    }
+}
+
+pub fn move_along_paths(mut query: Query<(&mut PathFollower, &mut Transform)>, time: Res<Time>) {
+    let delta = time.delta_seconds();
+    if delta <= 0.0 {
+        return;
+    }
+    for (mut follower, mut transform) in query.iter_mut() {
+        follower.travelled += follower.speed * delta;
+        let position = follower.position_at(follower.travelled);
+        follower.velocity = (position - transform.translation) / delta;
+        transform.translation = position;
+    }
+}
+
+pub fn spin_turntables(mut query: Query<(&Turntable, &mut Transform)>, time: Res<Time>) {
+    for (turntable, mut transform) in query.iter_mut() {
+        transform.rotate_y(turntable.angular_speed * time.delta_seconds());
+    }
+}
+
+// Works out how the floor under everything that moves is moving and hands it over as a
+// SurfaceVelocity for update_position to carry it along with. Platforms take things along their
+// path, conveyors along their length and turntables round their middle, turning them as well.
+#[allow(clippy::type_complexity)]
+pub fn carry_riders(
+    mut commands: Commands,
+    surface_query: Query<(
+        &Transform,
+        &MovingSurface,
+        Option<&PathFollower>,
+        Option<&Turntable>,
+        Option<&Conveyor>,
+    )>,
+    mut rider_query: Query<
+        Option<&mut SurfaceVelocity>,
+        (With<Velocity>, Without<Bullet>, Without<Parent>),
+    >,
+    spatial_hash: Res<SpatialHash>,
+    time: Res<Time>,
+) {
+    // nothing is carried unless it turns out to be on something
+    for mut surface_velocity in rider_query.iter_mut().flatten() {
+        *surface_velocity = SurfaceVelocity::default();
+    }
+    let delta = time.delta_seconds();
+    if delta <= 0.0 {
+        return;
+    }
+
+    for (transform, surface, follower, turntable, conveyor) in surface_query.iter() {
+        let centre = transform.translation;
+        for (rider, position) in spatial_hash.within(centre, surface.reach() + HASH_SLACK) {
+            let Ok(surface_velocity) = rider_query.get_mut(rider) else {
+                continue;
+            };
+            let local = transform.rotation.inverse() * (position - centre);
+            if !surface.contains(local) {
+                continue;
+            }
+
+            let mut carried = SurfaceVelocity::default();
+            if let Some(follower) = follower {
+                carried.linear += follower.velocity;
+            }
+            if let Some(conveyor) = conveyor {
+                carried.linear += transform.rotation * Vec3::Z * conveyor.speed;
+            }
+            if let Some(turntable) = turntable {
+                // the exact step round the circle this frame, following the tangent instead would
+                // slowly fling riders outwards
+                let offset = (position - centre).with_y(0.0);
+                let turn = Quat::from_rotation_y(turntable.angular_speed * delta);
+                carried.linear += (turn * offset - offset) / delta;
+                carried.angular += turntable.angular_speed;
+            }
+
+            match surface_velocity {
+                Some(mut surface_velocity) => *surface_velocity = carried,
+                None => {
+                    commands.entity(rider).insert(carried);
+                }
+            }
+        }
+    }
+}
+
+// slides the slats along each conveyor, wrapping round to the start when they reach the end
+pub fn scroll_conveyor_slats(
+    conveyor_query: Query<(&Conveyor, &Children)>,
+    mut slat_query: Query<&mut Transform, With<ConveyorSlat>>,
+    time: Res<Time>,
+) {
+    for (conveyor, children) in conveyor_query.iter() {
+        let half_length = conveyor.length * 0.5;
+        for child in children.iter() {
+            let Ok(mut transform) = slat_query.get_mut(*child) else {
+                continue;
+            };
+            let along = transform.translation.z + conveyor.speed * time.delta_seconds();
+            transform.translation.z =
+                (along + half_length).rem_euclid(conveyor.length) - half_length;
+        }
+    }
+}
@@ -40,3 +40,26 @@ impl Acceleration {
         Self { value }
     }
 }
+
+// Mass tells us how hard an entity is to push around, heavier entities get knocked back less
+
+#[derive(Component, Debug)]
+pub struct Mass {
+    pub value: f32,
+}
+
+impl Mass {
+    pub fn new(value: f32) -> Self {
+        Self { value }
+    }
+}
+
+// SurfaceVelocity is how the floor under an entity is moving, like a conveyor belt or a spinning
+// turntable. The entity gets carried along by it on top of its own velocity, and turned around by
+// angular so a robot on a turntable keeps facing the same way relative to the turntable.
+
+#[derive(Component, Debug, Default)]
+pub struct SurfaceVelocity {
+    pub linear: Vec3,
+    pub angular: f32, // radians per second around the vertical, anticlockwise seen from above
+}
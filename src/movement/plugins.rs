@@ -25,14 +25,21 @@ pub struct MovingObjBundle {
     pub health: Health,
     pub collider: Collider,
     pub collision: CollisionDamage,
+    pub mass: Mass,
 }
 
+// Moving everything by its velocity. Anything that changes how things move this frame (like the
+// floor carrying them along) should go before this.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ApplyMovement;
+
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
             (update_position, update_velocity)
                 .chain()
+                .in_set(ApplyMovement)
                 .in_set(InGameSet::EntityUpdates),
         )
         .add_systems(Update, block_movement_controls.in_set(InGameSet::UserInput));
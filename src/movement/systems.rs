@@ -1,6 +1,6 @@
 pub use bevy::prelude::*;
 
-use crate::entities::Block;
+use crate::{combat::StatusEffects, entities::Block};
 
 use super::*;
 
@@ -20,9 +20,26 @@ pub fn update_velocity(mut query: Query<(&Acceleration, &mut Velocity)>, time: R
 
 // We're going to do the same thing but for position
 
-pub fn update_position(mut query: Query<(&Velocity, &mut Transform)>, time: Res<Time>) {
-    for (velocity, mut transform) in query.iter_mut() {
-        transform.translation += velocity.value * time.delta_seconds();
+pub fn update_position(
+    mut query: Query<(
+        &Velocity,
+        &mut Transform,
+        Option<&StatusEffects>,
+        Option<&SurfaceVelocity>,
+    )>,
+    time: Res<Time>,
+) {
+    for (velocity, mut transform, status_effects, surface) in query.iter_mut() {
+        // a slowed entity only covers part of the distance its velocity asks for
+        let speed_factor =
+            status_effects.map_or(1.0, |status_effects| status_effects.speed_factor());
+        transform.translation += velocity.value * speed_factor * time.delta_seconds();
+        // whatever it's standing on carries it along no matter how slowed it is, and turns it too
+        // so the block's controls (which go by its rotation) turn along with a turntable
+        if let Some(surface) = surface {
+            transform.translation += surface.linear * time.delta_seconds();
+            transform.rotate_y(surface.angular * time.delta_seconds());
+        }
     }
 }
 // translation is the position of the entity, consider it the variable of the Transform component
@@ -39,17 +56,18 @@ pub fn update_position(mut query: Query<(&Velocity, &mut Transform)>, time: Res<
 
 const SPEED: f32 = 10.0;
 const ROTATION_SPEED: f32 = 2.5;
+const GRIP: f32 = 8.0; // how quickly the block's wheels bring it back to the speed we ask for
 
 // const means constant often capitalized to indiciate these variable will NEVER change
 // now lets bind some awsd buttons to our block to make it move :D
 
 pub fn block_movement_controls(
-    mut query: Query<(&mut Transform, &mut Velocity), With<Block>>, //With<Block> specifies we only want the
-    keyboard_input: Res<ButtonInput<KeyCode>>,                      //data from "Block" entities
+    mut query: Query<(&mut Transform, &mut Velocity, Option<&StatusEffects>), With<Block>>, //With<Block> specifies we only want the
+    keyboard_input: Res<ButtonInput<KeyCode>>, //data from "Block" entities
     time: Res<Time>,
 ) {
     //we're gonna make some local variables to help with our controller system
-    let Ok((mut transform, mut velocity)) = query.get_single_mut() else {
+    let Ok((mut transform, mut velocity, status_effects)) = query.get_single_mut() else {
         return;
     };
     //we use single_mut since we're only looking at the block we spawned, this will only work with
@@ -78,10 +96,21 @@ pub fn block_movement_controls(
       // we'll rotate the block over time to move with our forward and backward movement
       // or spin in place
 
+    //a stunned block ignores the controls and just rolls to a stop
+    if status_effects.is_some_and(|status_effects| status_effects.is_stunned()) {
+        movement = 0.0;
+        rotation = 0.0;
+    }
+
     //now lets update our variables based on button input
     transform.rotate_y(rotation); // this will rotate our block if buttons A or D are pressed
-    velocity.value = -transform.forward() * movement; //this updates velocity based on buttons S or W
-                                                      //being pressed
-                                                      //notice it says negative transform that's because the forward command indicates the negative Z value
-                                                      //so we use a 2nd negative to turn it positive
+    let target_velocity = -transform.forward() * movement; //this is the velocity buttons S or W ask for
+                                                           //notice it says negative transform that's because the forward command indicates the negative Z value
+                                                           //so we use a 2nd negative to turn it positive
+
+    //instead of snapping straight to the target velocity we blend towards it, this way a hit that
+    //knocks the block around (see combat) still shows up before the wheels catch it again
+    velocity.value = velocity
+        .value
+        .lerp(target_velocity, (GRIP * time.delta_seconds()).min(1.0));
 }
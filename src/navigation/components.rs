@@ -0,0 +1,43 @@
+use bevy::{prelude::*, tasks::Task};
+
+// Marks a collider the navigation grid should route around, like a pillar or a wall. Adding or
+// removing one rebuilds the grid.
+#[derive(Component, Debug, Default)]
+pub struct Obstacle;
+
+// Insert this to send an entity to goal, a path gets worked out in the background and turned into
+// a FollowPath once it's ready
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PathRequest {
+    pub goal: Vec3,
+    pub speed: f32,
+}
+
+impl PathRequest {
+    pub fn new(goal: Vec3, speed: f32) -> Self {
+        Self { goal, speed }
+    }
+}
+
+// a path that's still being worked out
+#[derive(Component)]
+pub struct PathTask {
+    pub task: Task<Option<Vec<Vec3>>>,
+    pub speed: f32,
+    pub grid_version: u32,
+}
+
+// Drives the entity's Velocity from waypoint to waypoint, it's removed once the last one is reached
+#[derive(Component, Debug, Clone)]
+pub struct FollowPath {
+    pub waypoints: Vec<Vec3>,
+    pub next: usize, // index of the waypoint it's heading for
+    pub speed: f32,
+    pub grid_version: u32, // the grid the path was planned on, a newer grid means planning again
+}
+
+impl FollowPath {
+    pub fn goal(&self) -> Option<Vec3> {
+        self.waypoints.last().copied()
+    }
+}
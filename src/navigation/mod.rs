@@ -0,0 +1,10 @@
+mod components;
+mod pathfinding;
+mod plugins;
+mod resources;
+mod systems;
+
+pub use components::*;
+pub use pathfinding::*;
+pub use plugins::*;
+pub use resources::*;
@@ -0,0 +1,256 @@
+// A* over the navigation grid (see Amit Patel's "Introduction to A*" for a friendly walkthrough).
+// Robots can move to any of the 8 cells around them but can't cut across the corner of a blocked
+// cell. The raw path goes cell by cell, so it's smoothed afterwards by skipping every waypoint it
+// can drive straight past.
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::prelude::*;
+
+use super::NavGrid;
+
+// costs are kept as whole numbers so they can go in the heap, a diagonal step is about 14/10 of a
+// straight one
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+// how far away to look for somewhere walkable when the start or goal is inside an obstacle
+const NEAREST_SEARCH_CELLS: i32 = 4;
+
+const NEIGHBOURS: [IVec2; 8] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+    IVec2::new(1, 1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 1),
+    IVec2::new(-1, -1),
+];
+
+// the cost of the cheapest possible route ignoring obstacles, so A* never overestimates
+fn octile_distance(from: IVec2, to: IVec2) -> u32 {
+    let difference = (to - from).abs();
+    let straight = difference.x.max(difference.y) - difference.x.min(difference.y);
+    let diagonal = difference.x.min(difference.y);
+    straight as u32 * STRAIGHT_COST + diagonal as u32 * DIAGONAL_COST
+}
+
+// the closest walkable cell, for when a robot is brushing up against an obstacle
+fn nearest_walkable(grid: &NavGrid, cell: IVec2) -> Option<IVec2> {
+    if grid.is_walkable(cell) {
+        return Some(cell);
+    }
+    (1..=NEAREST_SEARCH_CELLS).find_map(|ring| {
+        (-ring..=ring)
+            .flat_map(|x| (-ring..=ring).map(move |y| cell + IVec2::new(x, y)))
+            .filter(|candidate| grid.is_walkable(*candidate))
+            .min_by_key(|candidate| octile_distance(cell, *candidate))
+    })
+}
+
+// Returns the waypoints from start to goal, not including start. None if there's no way through.
+pub fn find_path(grid: &NavGrid, start: Vec3, goal: Vec3) -> Option<Vec<Vec3>> {
+    let start_cell = nearest_walkable(grid, grid.cell_at(start))?;
+    let goal_cell = nearest_walkable(grid, grid.cell_at(goal))?;
+    let cells = search(grid, start_cell, goal_cell)?;
+
+    let mut points: Vec<Vec3> = cells
+        .into_iter()
+        .map(|cell| grid.cell_centre(cell, start.y))
+        .collect();
+    // finish exactly on the goal if it's somewhere a robot can be
+    if grid.is_walkable(grid.cell_at(goal)) {
+        if let Some(last) = points.last_mut() {
+            *last = Vec3::new(goal.x, start.y, goal.z);
+        }
+    }
+    // start from exactly where the robot is, the first cell's centre stays in case it had to be
+    // moved out of an obstacle to get going
+    points.insert(0, start);
+    let mut path = smooth_path(grid, &points);
+    path.remove(0);
+    Some(path)
+}
+
+// every walkable cell a robot can step to from cell, and whether the step is diagonal
+fn walkable_neighbours(grid: &NavGrid, cell: IVec2) -> impl Iterator<Item = (IVec2, bool)> + '_ {
+    NEIGHBOURS.into_iter().filter_map(move |step| {
+        let next = cell + step;
+        if !grid.is_walkable(next) {
+            return None;
+        }
+        let diagonal = step.x != 0 && step.y != 0;
+        // squeezing diagonally between two blocked cells would clip their corners
+        if diagonal
+            && (!grid.is_walkable(cell + IVec2::new(step.x, 0))
+                || !grid.is_walkable(cell + IVec2::new(0, step.y)))
+        {
+            return None;
+        }
+        Some((next, diagonal))
+    })
+}
+
+// Flood fills out from start and marks every cell a robot could drive to from there, indexed the
+// same way as the grid's blocked cells. Nothing is reachable if start is inside an obstacle.
+pub fn reachable_cells(grid: &NavGrid, start: Vec3) -> Vec<bool> {
+    let index = |cell: IVec2| (cell.y * grid.columns + cell.x) as usize;
+    let mut reached = vec![false; (grid.columns * grid.rows) as usize];
+    let start = grid.cell_at(start);
+    if !grid.is_walkable(start) {
+        return reached;
+    }
+    reached[index(start)] = true;
+    let mut frontier = vec![start];
+    while let Some(cell) = frontier.pop() {
+        for (next, _) in walkable_neighbours(grid, cell) {
+            if !reached[index(next)] {
+                reached[index(next)] = true;
+                frontier.push(next);
+            }
+        }
+    }
+    reached
+}
+
+// plain A*, returns every cell along the way including the start and goal
+fn search(grid: &NavGrid, start: IVec2, goal: IVec2) -> Option<Vec<IVec2>> {
+    let size = (grid.columns * grid.rows) as usize;
+    let index = |cell: IVec2| (cell.y * grid.columns + cell.x) as usize;
+    let mut cost_so_far = vec![u32::MAX; size];
+    let mut came_from: Vec<Option<IVec2>> = vec![None; size];
+    let mut open = BinaryHeap::new();
+
+    cost_so_far[index(start)] = 0;
+    open.push(Reverse((octile_distance(start, goal), start.x, start.y)));
+
+    while let Some(Reverse((_, x, y))) = open.pop() {
+        let cell = IVec2::new(x, y);
+        if cell == goal {
+            let mut cells = vec![goal];
+            while let Some(previous) = came_from[index(*cells.last().unwrap())] {
+                cells.push(previous);
+            }
+            cells.reverse();
+            return Some(cells);
+        }
+        let cost = cost_so_far[index(cell)];
+        for (next, diagonal) in walkable_neighbours(grid, cell) {
+            let step_cost = if diagonal {
+                DIAGONAL_COST
+            } else {
+                STRAIGHT_COST
+            };
+            let next_cost = cost + step_cost;
+            if next_cost < cost_so_far[index(next)] {
+                cost_so_far[index(next)] = next_cost;
+                came_from[index(next)] = Some(cell);
+                let priority = next_cost + octile_distance(next, goal);
+                open.push(Reverse((priority, next.x, next.y)));
+            }
+        }
+    }
+    None
+}
+
+// Keeps the first point, then jumps to the furthest point it can see in a straight line, over and
+// over. Turns the staircase A* gives into a few straight legs.
+pub fn smooth_path(grid: &NavGrid, points: &[Vec3]) -> Vec<Vec3> {
+    let Some(first) = points.first() else {
+        return Vec::new();
+    };
+    let mut smoothed = vec![*first];
+    let mut anchor = 0;
+    while anchor < points.len() - 1 {
+        let furthest = (anchor + 1..points.len())
+            .rev()
+            .find(|candidate| is_clear_line(grid, points[anchor], points[*candidate]))
+            .unwrap_or(anchor + 1);
+        smoothed.push(points[furthest]);
+        anchor = furthest;
+    }
+    smoothed
+}
+
+// walks along the line in small steps checking every cell it passes over
+pub fn is_clear_line(grid: &NavGrid, from: Vec3, to: Vec3) -> bool {
+    let offset = Vec3::new(to.x - from.x, 0.0, to.z - from.z);
+    let steps = (offset.length() / (grid.cell_size * 0.25)).ceil().max(1.0) as u32;
+    (0..=steps).all(|step| {
+        let point = from + offset * (step as f32 / steps as f32);
+        grid.is_walkable(grid.cell_at(point))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 5 units across with a wall along z = 0 to 0.5, leaving a one unit gap at the +x end
+    fn walled_grid() -> NavGrid {
+        let mut grid = NavGrid::new(Vec2::ZERO, 2.5);
+        let mut blocked = vec![false; (grid.columns * grid.rows) as usize];
+        for x in 0..grid.columns - 2 {
+            blocked[(5 * grid.columns + x) as usize] = true;
+        }
+        grid.set_blocked(blocked);
+        grid
+    }
+
+    #[test]
+    fn goal_in_the_same_cell_is_kept() {
+        let grid = walled_grid();
+        let path = find_path(&grid, Vec3::new(0.1, 0.0, -2.0), Vec3::new(0.2, 0.0, -1.9));
+        assert_eq!(path, Some(vec![Vec3::new(0.2, 0.0, -1.9)]));
+    }
+
+    #[test]
+    fn path_goes_round_the_wall() {
+        let grid = walled_grid();
+        let start = Vec3::new(-2.0, 0.0, -2.0);
+        let goal = Vec3::new(-2.0, 0.0, 2.0);
+        let path = find_path(&grid, start, goal).expect("there's a gap in the wall");
+
+        assert_eq!(path.last(), Some(&goal));
+        assert!(path.iter().any(|point| point.x >= 1.5));
+        let mut from = start;
+        for point in path {
+            assert!(is_clear_line(&grid, from, point));
+            from = point;
+        }
+    }
+
+    #[test]
+    fn no_path_through_a_solid_wall() {
+        let mut grid = walled_grid();
+        let mut blocked = grid.blocked.to_vec();
+        for x in 0..grid.columns {
+            blocked[(5 * grid.columns + x) as usize] = true;
+        }
+        grid.set_blocked(blocked);
+        let path = find_path(&grid, Vec3::new(-2.0, 0.0, -2.0), Vec3::new(-2.0, 0.0, 2.0));
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn smoothing_straightens_open_ground() {
+        let grid = walled_grid();
+        let points: Vec<Vec3> = (0..6)
+            .map(|step| Vec3::new(-2.0 + step as f32 * 0.5, 0.0, -2.0))
+            .collect();
+        let smoothed = smooth_path(&grid, &points);
+        assert_eq!(smoothed, vec![points[0], points[5]]);
+        assert!(smooth_path(&grid, &[]).is_empty());
+    }
+
+    #[test]
+    fn smoothing_keeps_the_corner_round_the_wall() {
+        let grid = walled_grid();
+        let points = [
+            Vec3::new(-2.0, 0.0, -1.0),
+            Vec3::new(2.0, 0.0, -1.0),
+            Vec3::new(2.0, 0.0, 1.0),
+            Vec3::new(-2.0, 0.0, 1.0),
+        ];
+        assert_eq!(smooth_path(&grid, &points), points.to_vec());
+    }
+}
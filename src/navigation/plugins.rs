@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+use bevy_rapier3d::plugin::PhysicsSet;
+
+use crate::{schedule::InGameSet, settings::dev_tools_enabled};
+
+use super::systems::*;
+use super::{NavGrid, RebuildNavGrid};
+
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGrid>()
+            .add_event::<RebuildNavGrid>()
+            .add_systems(PostUpdate, rebuild_nav_grid.after(PhysicsSet::Writeback))
+            // following a path is just another set of controls, like the AI's steering
+            .add_systems(
+                Update,
+                (
+                    replan_stale_paths,
+                    start_path_requests,
+                    poll_path_tasks,
+                    follow_paths,
+                )
+                    .chain()
+                    .in_set(InGameSet::UserInput),
+            )
+            .add_systems(
+                Update,
+                draw_navigation
+                    .run_if(dev_tools_enabled)
+                    .after(InGameSet::EntityUpdates),
+            );
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+
+use super::find_path;
+
+// until an arena is built the grid covers a square this far out from the middle in every direction
+pub const NAV_GRID_HALF_EXTENT: f32 = 25.0;
+pub const NAV_CELL_SIZE: f32 = 0.5;
+// obstacles are grown by this much so paths leave room for a robot to get past
+pub const NAV_AGENT_RADIUS: f32 = 0.5;
+
+// Send this to rebuild the grid, e.g. after moving an obstacle. Adding or removing an Obstacle
+// does it automatically.
+#[derive(Event, Debug, Default)]
+pub struct RebuildNavGrid;
+
+// Which cells of the arena floor a robot can drive through. The cells are shared with an Arc so
+// a path can be worked out on another thread without copying the whole grid.
+#[derive(Resource, Debug, Clone)]
+pub struct NavGrid {
+    pub cell_size: f32,
+    pub columns: i32, // along x
+    pub rows: i32,    // along z
+    pub origin: Vec2, // the x/z corner of cell (0, 0)
+    pub blocked: Arc<Vec<bool>>,
+    pub version: u32, // goes up every rebuild
+}
+
+impl Default for NavGrid {
+    fn default() -> Self {
+        Self::new(Vec2::ZERO, NAV_GRID_HALF_EXTENT)
+    }
+}
+
+impl NavGrid {
+    // an empty grid covering a square half_extent out from centre in every direction
+    pub fn new(centre: Vec2, half_extent: f32) -> Self {
+        let cells = (half_extent * 2.0 / NAV_CELL_SIZE).ceil() as i32;
+        Self {
+            cell_size: NAV_CELL_SIZE,
+            columns: cells,
+            rows: cells,
+            origin: centre - Vec2::splat(half_extent),
+            blocked: Arc::new(vec![false; (cells * cells) as usize]),
+            version: 0,
+        }
+    }
+
+    pub fn in_bounds(&self, cell: IVec2) -> bool {
+        cell.x >= 0 && cell.y >= 0 && cell.x < self.columns && cell.y < self.rows
+    }
+
+    fn index(&self, cell: IVec2) -> usize {
+        (cell.y * self.columns + cell.x) as usize
+    }
+
+    pub fn is_walkable(&self, cell: IVec2) -> bool {
+        self.in_bounds(cell) && !self.blocked[self.index(cell)]
+    }
+
+    // the cell a point is in, y is ignored
+    pub fn cell_at(&self, position: Vec3) -> IVec2 {
+        let local = (Vec2::new(position.x, position.z) - self.origin) / self.cell_size;
+        local.floor().as_ivec2()
+    }
+
+    // the middle of a cell, at height y
+    pub fn cell_centre(&self, cell: IVec2, y: f32) -> Vec3 {
+        let centre = self.origin + (cell.as_vec2() + 0.5) * self.cell_size;
+        Vec3::new(centre.x, y, centre.y)
+    }
+
+    // swaps in a new set of blocked cells and bumps the version
+    pub fn set_blocked(&mut self, blocked: Vec<bool>) {
+        self.blocked = Arc::new(blocked);
+        self.version = self.version.wrapping_add(1);
+    }
+
+    // Works out a path on the async compute pool so a long search doesn't hold up the frame. Poll
+    // the task to get the waypoints, None means the goal can't be reached.
+    pub fn find_path_async(&self, start: Vec3, goal: Vec3) -> Task<Option<Vec<Vec3>>> {
+        let grid = self.clone();
+        AsyncComputeTaskPool::get().spawn(async move { find_path(&grid, start, goal) })
+    }
+}
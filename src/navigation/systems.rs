@@ -0,0 +1,174 @@
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future},
+};
+use bevy_rapier3d::prelude::{Collider, QueryFilter, RapierContext};
+
+use crate::movement::{Acceleration, Velocity};
+
+use super::*;
+
+// how close a robot has to get to a waypoint before heading for the next one
+const WAYPOINT_REACHED: f32 = 0.3;
+// cells are tested with a box this tall, centred this high off the floor
+const CELL_TEST_HEIGHT: f32 = 0.5;
+
+// Marks every cell whose area (grown by the agent radius) overlaps an obstacle's collider. Runs
+// after the physics step so obstacles spawned this frame are already in rapier's world.
+pub fn rebuild_nav_grid(
+    mut grid: ResMut<NavGrid>,
+    mut rebuild_events: EventReader<RebuildNavGrid>,
+    added: Query<(), Added<Obstacle>>,
+    mut removed: RemovedComponents<Obstacle>,
+    obstacles: Query<(), With<Obstacle>>,
+    rapier_context: Res<RapierContext>,
+) {
+    let requested = rebuild_events.read().count() > 0;
+    let changed = !added.is_empty() || removed.read().count() > 0;
+    if !requested && !changed {
+        return;
+    }
+
+    let half_size = grid.cell_size * 0.5 + NAV_AGENT_RADIUS;
+    let cell_shape = Collider::cuboid(half_size, CELL_TEST_HEIGHT * 0.5, half_size);
+    let is_obstacle = |entity| obstacles.contains(entity);
+    let filter = QueryFilter::default().predicate(&is_obstacle);
+
+    let mut blocked = Vec::with_capacity((grid.columns * grid.rows) as usize);
+    for row in 0..grid.rows {
+        for column in 0..grid.columns {
+            let centre = grid.cell_centre(IVec2::new(column, row), CELL_TEST_HEIGHT);
+            let hit =
+                rapier_context.intersection_with_shape(centre, Quat::IDENTITY, &cell_shape, filter);
+            blocked.push(hit.is_some());
+        }
+    }
+    grid.set_blocked(blocked);
+}
+
+// starts a background search for every new PathRequest
+pub fn start_path_requests(
+    mut commands: Commands,
+    query: Query<(Entity, &GlobalTransform, &PathRequest)>,
+    grid: Res<NavGrid>,
+) {
+    for (entity, transform, request) in query.iter() {
+        commands
+            .entity(entity)
+            .remove::<PathRequest>()
+            .insert(PathTask {
+                task: grid.find_path_async(transform.translation(), request.goal),
+                speed: request.speed,
+                grid_version: grid.version,
+            });
+    }
+}
+
+// hands finished searches over to FollowPath
+pub fn poll_path_tasks(mut commands: Commands, mut query: Query<(Entity, &mut PathTask)>) {
+    for (entity, mut path_task) in query.iter_mut() {
+        let Some(result) = block_on(future::poll_once(&mut path_task.task)) else {
+            continue;
+        };
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<PathTask>();
+        match result {
+            Some(waypoints) if !waypoints.is_empty() => {
+                entity_commands.insert(FollowPath {
+                    waypoints,
+                    next: 0,
+                    speed: path_task.speed,
+                    grid_version: path_task.grid_version,
+                });
+            }
+            _ => {
+                debug!("no path for {entity:?}");
+            }
+        }
+    }
+}
+
+// a path planned on an old grid might go through a new obstacle, so plan it again
+pub fn replan_stale_paths(
+    mut commands: Commands,
+    query: Query<(Entity, &FollowPath), Without<PathTask>>,
+    grid: Res<NavGrid>,
+) {
+    for (entity, path) in query.iter() {
+        if path.grid_version == grid.version {
+            continue;
+        }
+        if let Some(goal) = path.goal() {
+            commands
+                .entity(entity)
+                .insert(PathRequest::new(goal, path.speed));
+        }
+    }
+}
+
+// steers straight at the next waypoint at the path's speed, and stops at the end
+pub fn follow_paths(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut FollowPath,
+        &mut Transform,
+        &mut Velocity,
+        Option<&mut Acceleration>,
+    )>,
+) {
+    for (entity, mut path, mut transform, mut velocity, acceleration) in query.iter_mut() {
+        // the path decides the velocity, anything else pushing it would knock it off course
+        if let Some(mut acceleration) = acceleration {
+            acceleration.value = Vec3::ZERO;
+        }
+        let position = transform.translation;
+        while let Some(waypoint) = path.waypoints.get(path.next) {
+            let offset = Vec3::new(waypoint.x - position.x, 0.0, waypoint.z - position.z);
+            if offset.length() > WAYPOINT_REACHED {
+                break;
+            }
+            path.next += 1;
+        }
+        let Some(waypoint) = path.waypoints.get(path.next) else {
+            velocity.value = Vec3::ZERO;
+            commands.entity(entity).remove::<FollowPath>();
+            continue;
+        };
+        let heading = Vec3::new(waypoint.x - position.x, 0.0, waypoint.z - position.z).normalize();
+        velocity.value = heading * path.speed;
+        // the front of a robot is +z, the opposite of bevy's forward
+        transform.look_to(-heading, Vec3::Y);
+    }
+}
+
+// draws the blocked cells and every robot's route, only with dev tools on
+pub fn draw_navigation(
+    mut gizmos: Gizmos,
+    grid: Res<NavGrid>,
+    paths: Query<(&GlobalTransform, &FollowPath)>,
+) {
+    let cell_colour = Color::srgb(1.0, 0.3, 0.1);
+    let cell_size = Vec2::splat(grid.cell_size * 0.8);
+    let flat = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+    for row in 0..grid.rows {
+        for column in 0..grid.columns {
+            let cell = IVec2::new(column, row);
+            if !grid.is_walkable(cell) {
+                gizmos.rect(grid.cell_centre(cell, 0.02), flat, cell_size, cell_colour);
+            }
+        }
+    }
+
+    let path_colour = Color::srgb(0.2, 1.0, 0.4);
+    for (transform, path) in paths.iter() {
+        let remaining = path.waypoints.iter().skip(path.next).copied();
+        gizmos.linestrip(
+            std::iter::once(transform.translation()).chain(remaining),
+            path_colour,
+        );
+        for waypoint in path.waypoints.iter().skip(path.next) {
+            gizmos.sphere(*waypoint, Quat::IDENTITY, 0.1, path_colour);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// Things enemies drop when they're destroyed, drive the block over them to pick them up
+use crate::{combat::Health, entities::Block, round::RoundScoped, schedule::InGameSet};
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+const PICKUP_RADIUS: f32 = 1.0; // how close the block has to get to collect a pickup
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum LootItem {
+    Repair(f32), // gives the block back this much health
+}
+
+impl LootItem {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LootItem::Repair(_) => "Repair Kit",
+        }
+    }
+}
+
+// one entry in a loot table, chance is from 0.0 (never drops) to 1.0 (always drops)
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct LootDrop {
+    pub item: LootItem,
+    pub chance: f32,
+}
+
+// what an entity might drop when its health runs out
+#[derive(Component, Debug)]
+pub struct Loot(pub Vec<LootDrop>);
+
+#[derive(Component, Debug)]
+pub struct Pickup(pub LootItem);
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, drop_loot.in_set(InGameSet::DespawnEntities))
+            .add_systems(Update, collect_pickups.in_set(InGameSet::EntityUpdates));
+    }
+}
+
+// runs alongside despawn_dead_entities, which removes the dead entity once this has rolled its loot
+fn drop_loot(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query: Query<(&Health, &Loot, &GlobalTransform)>,
+) {
+    let mut rng = rand::thread_rng();
+    for (health, loot, transform) in query.iter() {
+        if health.value > 0.0 {
+            continue;
+        }
+        for drop in loot.0.iter() {
+            if rng.gen::<f32>() >= drop.chance {
+                continue;
+            }
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Sphere::new(0.2)),
+                    material: materials.add(Color::srgb(0.0, 1.0, 0.0)),
+                    transform: Transform::from_translation(transform.translation()),
+                    ..default()
+                },
+                Pickup(drop.item),
+                RoundScoped,
+                Name::new(drop.item.name()),
+            ));
+        }
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    pickup_query: Query<(Entity, &Pickup, &GlobalTransform)>,
+    mut block_query: Query<(&mut Health, &GlobalTransform), With<Block>>,
+) {
+    let Ok((mut health, block_transform)) = block_query.get_single_mut() else {
+        return;
+    };
+    for (entity, pickup, transform) in pickup_query.iter() {
+        if transform
+            .translation()
+            .distance(block_transform.translation())
+            > PICKUP_RADIUS
+        {
+            continue;
+        }
+        match pickup.0 {
+            LootItem::Repair(amount) => health.value += amount,
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,502 @@
+// Prefabs are named templates for everything that moves around the arena: the block, bullets,
+// props and so on. Instead of writing out MovingObjBundle + PbrBundle + a marker + a Name every
+// time, describe the entity once as a Prefab and spawn it by name with PrefabSpawner.
+//
+// Prefabs can be registered from code or loaded from assets/prefabs/*.prefab.ron. The files are
+// read with bevy reflection, so a file looks exactly like the output of the console's dump command.
+use crate::{
+    archetypes::{find_archetype, EnemyArchetype},
+    arena::OutOfBounds,
+    boss::BossPatterns,
+    combat::{
+        Blast, CollisionDamage, Health, HitZoneBundle, HitZoneKind, Ramming, StatusEffect,
+        StatusEffects, StatusOnHit, Team, Weapon, Weapons,
+    },
+    entities::{Block, Bullet, Dummy},
+    movement::{Acceleration, Mass, MovingObjBundle, Velocity},
+    navigation::Obstacle,
+    round::RoundScoped,
+    scene_collider::{ColliderGen, SceneCollider},
+};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+    reflect::serde::{TypedReflectDeserializer, TypedReflectSerializer},
+    utils::HashMap,
+};
+use bevy_rapier3d::prelude::{Collider, Sensor};
+use serde::de::DeserializeSeed;
+
+pub struct PrefabPlugin;
+
+impl Plugin for PrefabPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Prefab>()
+            .init_resource::<PrefabRegistry>()
+            .init_asset::<PrefabAsset>()
+            .init_asset_loader::<PrefabLoader>()
+            .add_systems(Update, register_prefab_files);
+    }
+}
+
+#[derive(Reflect, Debug, Clone)]
+pub enum PrefabModel {
+    None,
+    Cuboid {
+        size: Vec3,
+        colour: Color,
+    },
+    Sphere {
+        radius: f32,
+        colour: Color,
+    },
+    // an imported model like "test.glb#Scene0", if collider is set the prefab's collider gets
+    // replaced with one generated from the model once it has spawned
+    Scene {
+        path: String,
+        collider: Option<ColliderGen>,
+    },
+}
+
+#[derive(Reflect, Debug, Clone)]
+pub enum PrefabCollider {
+    Cuboid { half_size: Vec3 },
+    Ball { radius: f32 },
+    HitZones, // one compound collider made up of all of the prefab's hit zones
+}
+
+// which marker component the prefab gets
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefabRole {
+    Prop,
+    Player,     // Block
+    Enemy,      // Dummy
+    Projectile, // Bullet
+}
+
+impl PrefabRole {
+    // robots are stopped by the wall, anything else that gets out of the arena is gone
+    fn out_of_bounds(&self) -> OutOfBounds {
+        match self {
+            PrefabRole::Prop => OutOfBounds::Kill,
+            PrefabRole::Player => OutOfBounds::Clamp,
+            PrefabRole::Enemy => OutOfBounds::Bounce,
+            PrefabRole::Projectile => OutOfBounds::Despawn,
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Clone)]
+pub struct PrefabHitZone {
+    pub kind: HitZoneKind,
+    pub multiplier: f32,
+    pub centre: Vec3,
+    pub half_size: Vec3,
+}
+
+// Anything left out of a prefab file is filled in from Prefab::default()
+#[derive(Reflect, Debug, Clone)]
+#[reflect(Default)]
+pub struct Prefab {
+    pub name: String, // the Name the spawned entity gets
+    pub role: PrefabRole,
+    pub model: PrefabModel,
+    pub collider: PrefabCollider,
+    pub sensor: bool,
+    pub scale: f32,
+    pub health: f32,
+    pub collision_damage: f32,
+    pub mass: f32,
+    pub velocity: Vec3, // relative to the way the prefab is facing when it spawns
+    pub acceleration: Vec3,
+    pub team: Option<Team>,
+    pub ramming: Option<f32>, // ramming damage per unit of impact speed
+    pub status_effects: bool, // whether status effects can be applied to it
+    pub on_hit: Vec<StatusEffect>,
+    pub hit_zones: Vec<PrefabHitZone>,
+    pub weapons: Vec<Weapon>,
+    pub obstacle: bool,       // whether the navigation grid routes around it
+    pub blast: Option<Blast>, // goes off when a projectile hits something
+    pub out_of_bounds: Option<OutOfBounds>, // None picks one to suit the role
+}
+
+impl Default for Prefab {
+    fn default() -> Self {
+        Self {
+            name: "prefab".to_string(),
+            role: PrefabRole::Prop,
+            model: PrefabModel::None,
+            collider: PrefabCollider::Ball { radius: 0.5 },
+            sensor: false,
+            scale: 1.0,
+            health: 100.0,
+            collision_damage: 0.0,
+            mass: 1.0,
+            velocity: Vec3::ZERO,
+            acceleration: Vec3::ZERO,
+            team: None,
+            ramming: None,
+            status_effects: false,
+            on_hit: Vec::new(),
+            hit_zones: Vec::new(),
+            weapons: Vec::new(),
+            obstacle: false,
+            blast: None,
+            out_of_bounds: None,
+        }
+    }
+}
+
+// Things that can be changed for a single spawn without touching the prefab
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrefabOverrides {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub velocity: Option<Vec3>, // replaces the prefab's velocity, in world space
+    pub team: Option<Team>,
+    pub health: Option<f32>,
+}
+
+impl PrefabOverrides {
+    pub fn at(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..default()
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct PrefabRegistry {
+    prefabs: HashMap<String, Prefab>,
+}
+
+impl PrefabRegistry {
+    pub fn register(&mut self, id: impl Into<String>, prefab: Prefab) {
+        self.prefabs.insert(id.into(), prefab);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Prefab> {
+        self.prefabs.get(id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &String> {
+        self.prefabs.keys()
+    }
+}
+
+// Spawns prefabs and enemy archetypes by name. Enemy archetypes are turned into a prefab first and
+// then get their extra components on top.
+#[derive(SystemParam)]
+pub struct PrefabSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    registry: Res<'w, PrefabRegistry>,
+    archetypes: Res<'w, Assets<EnemyArchetype>>,
+    asset_server: Res<'w, AssetServer>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+}
+
+impl PrefabSpawner<'_, '_> {
+    // Returns None and logs a warning if there's nothing called id
+    pub fn spawn(&mut self, id: &str, overrides: PrefabOverrides) -> Option<EntityCommands<'_>> {
+        if let Some(prefab) = self.registry.get(id).cloned() {
+            return Some(self.spawn_prefab(&prefab, overrides));
+        }
+        if let Some(archetype) = find_archetype(&self.archetypes, id) {
+            let prefab = archetype.prefab();
+            let extras = archetype.components();
+            let boss = archetype.boss.clone();
+            let mut enemy = self.spawn_prefab(&prefab, overrides);
+            enemy.insert(extras);
+            if let Some(script) = boss {
+                enemy.insert(BossPatterns::new(script));
+            }
+            return Some(enemy);
+        }
+        warn!("no prefab or enemy archetype called {id}");
+        None
+    }
+
+    // every name spawn accepts, sorted
+    pub fn ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .registry
+            .ids()
+            .cloned()
+            .chain(
+                self.archetypes
+                    .iter()
+                    .map(|(_, archetype)| archetype.name.clone()),
+            )
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    pub fn spawn_prefab(
+        &mut self,
+        prefab: &Prefab,
+        overrides: PrefabOverrides,
+    ) -> EntityCommands<'_> {
+        let transform = Transform {
+            translation: overrides.translation,
+            rotation: overrides.rotation,
+            scale: Vec3::splat(prefab.scale),
+        };
+        let collider = match &prefab.collider {
+            PrefabCollider::Cuboid { half_size } => {
+                Collider::cuboid(half_size.x, half_size.y, half_size.z)
+            }
+            PrefabCollider::Ball { radius } => Collider::ball(*radius),
+            PrefabCollider::HitZones => Collider::compound(
+                prefab
+                    .hit_zones
+                    .iter()
+                    .map(|zone| {
+                        let half_size = zone.half_size;
+                        let shape = Collider::cuboid(half_size.x, half_size.y, half_size.z);
+                        (zone.centre, Quat::IDENTITY, shape)
+                    })
+                    .collect(),
+            ),
+        };
+
+        let mut entity = self.commands.spawn((
+            MovingObjBundle {
+                velocity: Velocity::new(
+                    overrides
+                        .velocity
+                        .unwrap_or(overrides.rotation * prefab.velocity),
+                ),
+                acceleration: Acceleration::new(overrides.rotation * prefab.acceleration),
+                health: Health::new(overrides.health.unwrap_or(prefab.health)),
+                collider,
+                collision: CollisionDamage(prefab.collision_damage),
+                mass: Mass::new(prefab.mass),
+            },
+            RoundScoped,
+            Name::new(prefab.name.clone()),
+        ));
+
+        match &prefab.model {
+            PrefabModel::None => {
+                entity.insert(SpatialBundle::from_transform(transform));
+            }
+            PrefabModel::Cuboid { size, colour } => {
+                entity.insert(PbrBundle {
+                    mesh: self.meshes.add(Cuboid::from_size(*size)),
+                    material: self.materials.add(*colour),
+                    transform,
+                    ..default()
+                });
+            }
+            PrefabModel::Sphere { radius, colour } => {
+                entity.insert(PbrBundle {
+                    mesh: self.meshes.add(Sphere::new(*radius)),
+                    material: self.materials.add(*colour),
+                    transform,
+                    ..default()
+                });
+            }
+            PrefabModel::Scene { path, collider } => {
+                entity.insert(SceneBundle {
+                    scene: self.asset_server.load(path.clone()),
+                    transform,
+                    ..default()
+                });
+                if let Some(collider) = collider {
+                    entity.insert(SceneCollider(*collider));
+                }
+            }
+        }
+
+        match prefab.role {
+            PrefabRole::Prop => (),
+            PrefabRole::Player => {
+                entity.insert(Block);
+            }
+            PrefabRole::Enemy => {
+                entity.insert(Dummy);
+            }
+            PrefabRole::Projectile => {
+                entity.insert(Bullet);
+            }
+        }
+        entity.insert(prefab.out_of_bounds.unwrap_or(prefab.role.out_of_bounds()));
+        if prefab.sensor {
+            entity.insert(Sensor);
+        }
+        if let Some(team) = overrides.team.or(prefab.team) {
+            entity.insert(team);
+        }
+        if let Some(damage_per_speed) = prefab.ramming {
+            entity.insert(Ramming::new(damage_per_speed));
+        }
+        if prefab.status_effects {
+            entity.insert(StatusEffects::default());
+        }
+        if !prefab.weapons.is_empty() {
+            entity.insert(Weapons(prefab.weapons.clone()));
+        }
+        if prefab.obstacle {
+            entity.insert(Obstacle);
+        }
+        if let Some(blast) = prefab.blast {
+            entity.insert(blast);
+        }
+        if !prefab.on_hit.is_empty() {
+            entity.insert(StatusOnHit(prefab.on_hit.clone()));
+        }
+        if !prefab.hit_zones.is_empty() {
+            entity.with_children(|parent| {
+                for zone in prefab.hit_zones.iter() {
+                    let half_size = zone.half_size;
+                    parent.spawn(HitZoneBundle::new(
+                        zone.kind,
+                        zone.multiplier,
+                        Collider::cuboid(half_size.x, half_size.y, half_size.z),
+                        zone.centre,
+                    ));
+                }
+            });
+        }
+        entity
+    }
+}
+
+// turns a prefab into the same text a prefab file holds
+pub fn prefab_to_ron(
+    prefab: &Prefab,
+    type_registry: &AppTypeRegistry,
+) -> Result<String, ron::Error> {
+    let type_registry = type_registry.read();
+    let serializer = TypedReflectSerializer::new(prefab, &type_registry);
+    ron::ser::to_string_pretty(&serializer, ron::ser::PrettyConfig::default())
+}
+
+// a prefab loaded from a file, id is the file name without .prefab.ron
+#[derive(Asset, TypePath, Debug)]
+pub struct PrefabAsset {
+    pub id: String,
+    pub prefab: Prefab,
+}
+
+pub struct PrefabLoader {
+    type_registry: AppTypeRegistry,
+}
+
+impl FromWorld for PrefabLoader {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            type_registry: world.resource::<AppTypeRegistry>().clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PrefabLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Invalid, // parsed fine but doesn't fit in a Prefab
+}
+
+impl std::fmt::Display for PrefabLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrefabLoaderError::Io(error) => write!(f, "could not read prefab: {error}"),
+            PrefabLoaderError::Ron(error) => write!(f, "invalid prefab: {error}"),
+            PrefabLoaderError::Invalid => write!(f, "invalid prefab: does not match Prefab"),
+        }
+    }
+}
+
+impl std::error::Error for PrefabLoaderError {}
+
+impl AssetLoader for PrefabLoader {
+    type Asset = PrefabAsset;
+    type Settings = ();
+    type Error = PrefabLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<PrefabAsset, PrefabLoaderError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(PrefabLoaderError::Io)?;
+
+        let prefab = {
+            let type_registry = self.type_registry.read();
+            let registration = type_registry
+                .get(std::any::TypeId::of::<Prefab>())
+                .ok_or(PrefabLoaderError::Invalid)?;
+            let mut deserializer =
+                ron::de::Deserializer::from_bytes(&bytes).map_err(PrefabLoaderError::Ron)?;
+            let reflected = TypedReflectDeserializer::new(registration, &type_registry)
+                .deserialize(&mut deserializer)
+                .map_err(|error| PrefabLoaderError::Ron(deserializer.span_error(error)))?;
+            Prefab::from_reflect(&*reflected).ok_or(PrefabLoaderError::Invalid)?
+        };
+
+        let id = load_context
+            .path()
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or("prefab", |name| name.trim_end_matches(".prefab.ron"))
+            .to_string();
+        Ok(PrefabAsset { id, prefab })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["prefab.ron"]
+    }
+}
+
+// prefab files go into the registry as soon as they've loaded, and again whenever they change
+fn register_prefab_files(
+    mut asset_events: EventReader<AssetEvent<PrefabAsset>>,
+    prefab_assets: Res<Assets<PrefabAsset>>,
+    mut registry: ResMut<PrefabRegistry>,
+) {
+    for event in asset_events.read() {
+        if let AssetEvent::Added { id } | AssetEvent::Modified { id } = event {
+            if let Some(asset) = prefab_assets.get(*id) {
+                registry.register(asset.id.clone(), asset.prefab.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_prefab_file_parses() {
+        let mut type_registry = bevy::reflect::TypeRegistry::default();
+        type_registry.register::<Prefab>();
+        let registration = type_registry.get(std::any::TypeId::of::<Prefab>()).unwrap();
+        let folder = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/prefabs");
+        for entry in std::fs::read_dir(folder).unwrap() {
+            let path = entry.unwrap().path();
+            let text = std::fs::read_to_string(&path).unwrap();
+            let mut deserializer = ron::de::Deserializer::from_str(&text).unwrap();
+            let reflected = TypedReflectDeserializer::new(registration, &type_registry)
+                .deserialize(&mut deserializer)
+                .unwrap_or_else(|error| {
+                    panic!("{} is not a valid prefab: {error}", path.display())
+                });
+            assert!(
+                Prefab::from_reflect(&*reflected).is_some(),
+                "{} is not a valid prefab",
+                path.display()
+            );
+        }
+    }
+}
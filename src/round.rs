@@ -0,0 +1,136 @@
+use crate::{
+    arena::{BuildArena, CurrentArena},
+    block::spawn_block,
+    movement::Acceleration,
+    prefabs::{PrefabOverrides, PrefabSpawner},
+    states::GameState,
+    stats::MatchStats,
+};
+use bevy::prelude::*;
+
+// Anything spawned as part of a round gets this marker so the whole round can be torn down in one
+// go before the next one starts
+#[derive(Component, Debug)]
+pub struct RoundScoped;
+
+#[derive(Debug, Clone)]
+pub struct DummySpawn {
+    pub archetype: String,
+    pub position: Vec3,
+    pub velocity: Vec3,
+}
+
+// Describes how a round starts. Nothing in here is random, so every round starts out exactly the same.
+// Where the player starts comes from the arena.
+#[derive(Debug, Clone)]
+pub struct RoundDefinition {
+    pub initial_wave: Vec<DummySpawn>,
+}
+
+impl Default for RoundDefinition {
+    fn default() -> Self {
+        Self {
+            initial_wave: vec![
+                DummySpawn {
+                    archetype: "roaming_dummy".to_string(),
+                    position: Vec3::new(-4.0, 0.5, 4.0),
+                    velocity: Vec3::new(1.0, 0.0, 0.0),
+                },
+                DummySpawn {
+                    archetype: "roaming_dummy".to_string(),
+                    position: Vec3::new(4.0, 0.5, 4.0),
+                    velocity: Vec3::new(-1.0, 0.0, 0.0),
+                },
+                DummySpawn {
+                    archetype: "fixed_dummy".to_string(),
+                    position: Vec3::new(5.0, 0.5, 5.0),
+                    velocity: Vec3::ZERO,
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RoundStats {
+    pub round: u32,
+    pub stats: MatchStats,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct RoundManager {
+    pub round: u32, // the round being played, 0 before the first one starts
+    pub definition: RoundDefinition,
+    pub history: Vec<RoundStats>, // stats from every round that's already finished
+}
+
+pub struct RoundPlugin;
+
+impl Plugin for RoundPlugin {
+    fn build(&self, app: &mut App) {
+        // chaining makes sure the old round is completely gone before anything new is spawned
+        app.init_resource::<RoundManager>()
+            // only when loading finishes into a game, backing out to the menu shouldn't start one
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Loading,
+                    entered: GameState::InGame,
+                },
+                (teardown_round, start_round).chain().after(BuildArena),
+            )
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                (teardown_round, reset_rounds).chain(),
+            );
+    }
+}
+
+fn teardown_round(mut commands: Commands, query: Query<Entity, With<RoundScoped>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// saves the stats of the round that just ended, then spawns the player followed by the initial wave
+fn start_round(
+    mut prefabs: PrefabSpawner,
+    mut rounds: ResMut<RoundManager>,
+    mut stats: ResMut<MatchStats>,
+    arena: Res<CurrentArena>,
+) {
+    if rounds.round > 0 {
+        let finished = RoundStats {
+            round: rounds.round,
+            stats: std::mem::take(&mut *stats),
+        };
+        rounds.history.push(finished);
+    }
+    rounds.round += 1;
+    stats.arena_seed = arena.0.seed;
+
+    spawn_block(&mut prefabs, arena.0.player_spawn());
+    for prop in arena.0.props.iter() {
+        let overrides = PrefabOverrides {
+            rotation: Quat::from_rotation_y(prop.rotation.to_radians()),
+            team: prop.team,
+            ..PrefabOverrides::at(Vec3::from(prop.position))
+        };
+        prefabs.spawn(&prop.prefab, overrides);
+    }
+    for spawn in rounds.definition.initial_wave.iter() {
+        // the archetype would pick a random direction, the round sets it so it's always the same
+        let overrides = PrefabOverrides {
+            velocity: Some(spawn.velocity),
+            ..PrefabOverrides::at(spawn.position)
+        };
+        if let Some(mut dummy) = prefabs.spawn(&spawn.archetype, overrides) {
+            dummy.insert(Acceleration::new(Vec3::ZERO));
+        }
+    }
+}
+
+fn reset_rounds(mut rounds: ResMut<RoundManager>, mut stats: ResMut<MatchStats>) {
+    rounds.round = 0;
+    rounds.history.clear();
+    *stats = MatchStats::default();
+}
@@ -0,0 +1,243 @@
+// Builds colliders for imported glTF models from their actual mesh data, so they collide with the
+// shape you see instead of a hand typed box
+use bevy::{
+    gltf::GltfExtras,
+    prelude::*,
+    render::mesh::{Indices, VertexAttributeValues},
+    scene::SceneInstanceReady,
+};
+use bevy_rapier3d::prelude::Collider;
+use serde::Deserialize;
+
+pub struct SceneColliderPlugin;
+
+impl Plugin for SceneColliderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, generate_scene_colliders);
+    }
+}
+
+// How the collider for an imported scene gets built
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Reflect)]
+#[serde(rename_all = "snake_case")]
+pub enum ColliderGen {
+    #[default]
+    ConvexHull, // one convex shape wrapped around the whole model, cheapest
+    ConvexDecomposition, // split into several convex pieces, follows concave models better
+    TriMesh,             // the exact triangles, most accurate but the most expensive
+}
+
+impl ColliderGen {
+    // names used in the glTF extras, e.g. { "collider": "trimesh" }
+    fn from_extras(value: &str) -> Option<Self> {
+        match value {
+            "convex_hull" => Some(ColliderGen::ConvexHull),
+            "convex_decomposition" => Some(ColliderGen::ConvexDecomposition),
+            "trimesh" => Some(ColliderGen::TriMesh),
+            _ => None,
+        }
+    }
+}
+
+// Put this on an entity with a SceneBundle to replace its collider with one generated from the
+// scene's meshes once the scene has spawned
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SceneCollider(pub ColliderGen);
+
+// any node whose name starts with this is a collider override, when a model has override nodes
+// only those are used for the collider and they're hidden from view
+const OVERRIDE_NODE_PREFIX: &str = "collider";
+
+// Per mesh settings read from the glTF. Extras look like { "collider": "none" } to skip a mesh or
+// { "collider": "trimesh" } to pick a different shape for it.
+struct MeshColliderSettings {
+    is_override: bool,
+    skip: bool,
+    shape: Option<ColliderGen>,
+}
+
+#[derive(Default)]
+struct MeshBuffers {
+    vertices: Vec<Vec3>,
+    indices: Vec<[u32; 3]>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn generate_scene_colliders(
+    mut commands: Commands,
+    mut ready_events: EventReader<SceneInstanceReady>,
+    roots: Query<&SceneCollider>,
+    children: Query<&Children>,
+    parents: Query<&Parent>,
+    node_query: Query<(&Transform, Option<&Name>, Option<&GltfExtras>)>,
+    mesh_handles: Query<&Handle<Mesh>>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    for event in ready_events.read() {
+        let root = event.parent;
+        let Ok(scene_collider) = roots.get(root) else {
+            continue;
+        };
+
+        let scene_meshes: Vec<(Entity, MeshColliderSettings)> = children
+            .iter_descendants(root)
+            .filter(|entity| mesh_handles.contains(*entity))
+            .map(|entity| {
+                let settings = mesh_settings(entity, root, &parents, &node_query);
+                (entity, settings)
+            })
+            .collect();
+        let has_overrides = scene_meshes
+            .iter()
+            .any(|(_, settings)| settings.is_override);
+
+        let mut buffers: Vec<(ColliderGen, MeshBuffers)> = Vec::new();
+        for (entity, settings) in scene_meshes {
+            if settings.skip || (has_overrides && !settings.is_override) {
+                continue;
+            }
+            if settings.is_override {
+                commands.entity(entity).insert(Visibility::Hidden);
+            }
+            let Some(mesh) = mesh_handles
+                .get(entity)
+                .ok()
+                .and_then(|handle| meshes.get(handle))
+            else {
+                continue;
+            };
+
+            let shape = settings.shape.unwrap_or(scene_collider.0);
+            let index = match buffers.iter().position(|(gen, _)| *gen == shape) {
+                Some(index) => index,
+                None => {
+                    buffers.push((shape, MeshBuffers::default()));
+                    buffers.len() - 1
+                }
+            };
+            let to_root = transform_to_root(entity, root, &parents, &node_query);
+            append_mesh(&mut buffers[index].1, mesh, to_root);
+        }
+
+        let mut colliders: Vec<Collider> = buffers
+            .into_iter()
+            .filter_map(|(shape, buffers)| build_collider(shape, buffers))
+            .collect();
+        let collider = match colliders.len() {
+            0 => {
+                warn!("could not generate a collider for scene on {root:?}, keeping its old one");
+                continue;
+            }
+            1 => colliders.remove(0),
+            _ => Collider::compound(
+                colliders
+                    .into_iter()
+                    .map(|collider| (Vec3::ZERO, Quat::IDENTITY, collider))
+                    .collect(),
+            ),
+        };
+        commands.entity(root).insert(collider);
+    }
+}
+
+// looks at the mesh and every node above it (up to the scene root) for override names and extras
+fn mesh_settings(
+    entity: Entity,
+    root: Entity,
+    parents: &Query<&Parent>,
+    node_query: &Query<(&Transform, Option<&Name>, Option<&GltfExtras>)>,
+) -> MeshColliderSettings {
+    let mut settings = MeshColliderSettings {
+        is_override: false,
+        skip: false,
+        shape: None,
+    };
+    for node in std::iter::once(entity).chain(parents.iter_ancestors(entity)) {
+        if node == root {
+            break;
+        }
+        let Ok((_, name, extras)) = node_query.get(node) else {
+            continue;
+        };
+        if name.is_some_and(|name| {
+            name.as_str()
+                .to_lowercase()
+                .starts_with(OVERRIDE_NODE_PREFIX)
+        }) {
+            settings.is_override = true;
+        }
+        let collider_extra = extras
+            .and_then(|extras| serde_json::from_str::<serde_json::Value>(&extras.value).ok())
+            .and_then(|value| value.get("collider")?.as_str().map(str::to_owned));
+        if let Some(value) = collider_extra {
+            if settings.shape.is_none() && !settings.skip {
+                settings.skip = value == "none";
+                settings.shape = ColliderGen::from_extras(&value);
+            }
+        }
+    }
+    settings
+}
+
+// the mesh's transform relative to the scene root, built from the local transforms so it doesn't
+// depend on transform propagation having run yet
+fn transform_to_root(
+    entity: Entity,
+    root: Entity,
+    parents: &Query<&Parent>,
+    node_query: &Query<(&Transform, Option<&Name>, Option<&GltfExtras>)>,
+) -> Mat4 {
+    let mut matrix = Mat4::IDENTITY;
+    for node in std::iter::once(entity).chain(parents.iter_ancestors(entity)) {
+        if node == root {
+            break;
+        }
+        if let Ok((transform, ..)) = node_query.get(node) {
+            matrix = transform.compute_matrix() * matrix;
+        }
+    }
+    matrix
+}
+
+fn append_mesh(buffers: &mut MeshBuffers, mesh: &Mesh, to_root: Mat4) {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+    let offset = buffers.vertices.len() as u32;
+    buffers.vertices.extend(
+        positions
+            .iter()
+            .map(|position| to_root.transform_point3(Vec3::from(*position))),
+    );
+
+    let indices: Vec<u32> = match mesh.indices() {
+        Some(Indices::U16(indices)) => indices.iter().map(|index| *index as u32).collect(),
+        Some(Indices::U32(indices)) => indices.clone(),
+        None => (0..positions.len() as u32).collect(),
+    };
+    buffers
+        .indices
+        .extend(indices.chunks_exact(3).map(|triangle| {
+            [
+                triangle[0] + offset,
+                triangle[1] + offset,
+                triangle[2] + offset,
+            ]
+        }));
+}
+
+fn build_collider(shape: ColliderGen, buffers: MeshBuffers) -> Option<Collider> {
+    if buffers.vertices.is_empty() {
+        return None;
+    }
+    match shape {
+        ColliderGen::ConvexHull => Collider::convex_hull(&buffers.vertices),
+        ColliderGen::ConvexDecomposition => Some(Collider::convex_decomposition(
+            &buffers.vertices,
+            &buffers.indices,
+        )),
+        ColliderGen::TriMesh => Some(Collider::trimesh(buffers.vertices, buffers.indices)),
+    }
+}
@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use bevy_rapier3d::render::DebugRenderContext;
+
+// Options the player can change from the pause menu
+#[derive(Resource, Debug)]
+pub struct GameSettings {
+    pub show_physics_debug: bool,
+    pub show_inspector: bool,
+    pub dev_tools: bool, // debugging tools like spawning dummies by hand with J and K
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            show_physics_debug: true,
+            show_inspector: true,
+            dev_tools: false,
+        }
+    }
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameSettings>().add_systems(
+            Update,
+            apply_physics_debug_setting.run_if(resource_changed::<GameSettings>),
+        );
+    }
+}
+
+fn apply_physics_debug_setting(
+    settings: Res<GameSettings>,
+    mut debug_render: ResMut<DebugRenderContext>,
+) {
+    debug_render.enabled = settings.show_physics_debug;
+}
+
+// run condition for the world inspector window
+pub fn inspector_enabled(settings: Res<GameSettings>) -> bool {
+    settings.show_inspector
+}
+
+// run condition for anything that should only work with dev tools turned on
+pub fn dev_tools_enabled(settings: Res<GameSettings>) -> bool {
+    settings.dev_tools
+}
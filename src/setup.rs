@@ -1,7 +1,5 @@
 //simple world create
 use bevy::prelude::*;
-// use crate::{movement::*, combat::*, entities::Dummy};
-// use bevy_rapier3d::prelude::Collider;
 pub struct StartingWorldPlugin;
 
 impl Plugin for StartingWorldPlugin {
@@ -14,51 +12,15 @@ impl Plugin for StartingWorldPlugin {
 // =======================================================================================
 
 /// set up a simple 3D scene
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
-    // circular base
-    commands.spawn(PbrBundle {
-        mesh: meshes.add(Circle::new(100.0)),
-        material: materials.add(Color::WHITE),
-        transform: Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
-        ..default()
-    });
-    // cube
-    // light
-    commands.spawn(PointLightBundle {
-        point_light: PointLight {
-            shadows_enabled: true,
-            ..default()
-        },
-        transform: Transform::from_xyz(4.0, 8.0, 4.0),
-        ..default()
-    });
-    // camera
+fn setup(mut commands: Commands) {
+    // the floor, the lights and everything else in the world come from an arena file once the game
+    // starts, see assets/arenas and arena/definition.rs
+    // camera, the arena moves it to one of its camera presets
     commands.spawn(Camera3dBundle {
         transform: Transform::from_xyz(-10.5, 7.5, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
         ..default()
     });
 
-    // uncomment below to test stopped dummy entity for collision
-
-    // commands.spawn((
-    //     MovingObjBundle {
-    //         velocity: Velocity::new(Vec3::ZERO),
-    //         acceleration: Acceleration::new(Vec3::ZERO),
-    //         health: Health::new(10000.0),
-    //         collider: Collider::cuboid(0.5,0.5,0.5),
-    //         collision: CollisionDamage(35.0),
-    //     },
-    //     PbrBundle {
-    //         mesh: meshes.add(Cuboid::new(1.0, 1.0, 1.0)), // makes the cube
-    //         material: materials.add(Color::srgb(255.0, 0.0, 0.0)), // makes the cube black
-    //         transform: Transform::from_xyz(5.0,0.5,5.0),
-    //         ..default() // Transform describes the position of the block
-    //     },
-    //     Dummy,
-    //     Name::new("Fixed Block"),
-    // ));
+    // to test a stopped dummy entity for collision turn on dev tools in the pause menu and type
+    // `spawn fixed_dummy 5 0.5 5` into the console, see console.rs
 }
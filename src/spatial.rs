@@ -0,0 +1,99 @@
+// A spatial hash buckets every moving entity by which square of the floor it's over, so "what's
+// near here?" only has to look at a few buckets instead of every entity in the arena. It's rebuilt
+// from GlobalTransform at the start of every frame, before anything in the game reads it.
+use crate::{movement::Velocity, states::GameState};
+use bevy::{prelude::*, utils::HashMap};
+
+// roughly the size of a robot, queries look at every bucket their radius touches
+const SPATIAL_CELL_SIZE: f32 = 2.0;
+
+#[derive(Resource, Debug)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec3)>>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(SPATIAL_CELL_SIZE)
+    }
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell_at(&self, position: Vec3) -> IVec2 {
+        (Vec2::new(position.x, position.z) / self.cell_size)
+            .floor()
+            .as_ivec2()
+    }
+
+    pub fn clear(&mut self) {
+        // emptying the buckets instead of dropping them saves reallocating them every frame
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec3) {
+        let cell = self.cell_at(position);
+        self.cells.entry(cell).or_default().push((entity, position));
+    }
+
+    // every entity within radius of position, along with where it was when the hash was built
+    pub fn within(&self, position: Vec3, radius: f32) -> impl Iterator<Item = (Entity, Vec3)> + '_ {
+        let min = self.cell_at(position - Vec3::splat(radius));
+        let max = self.cell_at(position + Vec3::splat(radius));
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, other)| other.distance_squared(position) <= radius * radius)
+    }
+
+    // Every entity further than radius from centre. Buckets that sit entirely inside the radius
+    // are skipped without looking at what's in them.
+    pub fn beyond(&self, centre: Vec3, radius: f32) -> impl Iterator<Item = (Entity, Vec3)> + '_ {
+        let centre_2d = Vec2::new(centre.x, centre.z);
+        self.cells
+            .iter()
+            .filter(move |(cell, _)| {
+                let corner = cell.as_vec2() * self.cell_size - centre_2d;
+                let far_x = corner.x.abs().max((corner.x + self.cell_size).abs());
+                let far_z = corner.y.abs().max((corner.y + self.cell_size).abs());
+                Vec2::new(far_x, far_z).length() > radius
+            })
+            .flat_map(|(_, bucket)| bucket.iter().copied())
+            .filter(move |(_, position)| position.distance(centre) > radius)
+    }
+}
+
+pub struct SpatialHashPlugin;
+
+impl Plugin for SpatialHashPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialHash>().add_systems(
+            PreUpdate,
+            update_spatial_hash.run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+// Only top level entities that move go in, a robot's hit zones move with it so they'd only be
+// duplicates
+#[allow(clippy::type_complexity)]
+fn update_spatial_hash(
+    mut spatial_hash: ResMut<SpatialHash>,
+    query: Query<(Entity, &GlobalTransform), (With<Velocity>, Without<Parent>)>,
+) {
+    spatial_hash.clear();
+    for (entity, transform) in query.iter() {
+        spatial_hash.insert(entity, transform.translation());
+    }
+}
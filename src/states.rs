@@ -3,39 +3,88 @@ use bevy::prelude::*;
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
     #[default]
+    MainMenu,
+    Loading,
     InGame,
     Paused,
     GameOver,
 }
 
+// Everything that moves the game between states sends one of these instead of setting the state
+// itself, so all of the allowed transitions live in handle_game_flow_events
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameFlowEvent {
+    StartGame,
+    LoadingFinished,
+    Pause,
+    Resume,
+    PlayerDefeated,
+    Retry,
+    ReturnToMenu,
+}
+
 pub struct StatePlugin;
 
 impl Plugin for StatePlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<GameState>().add_systems(
-            Update,
-            (
-                game_state_input_events,
-                transition_to_in_game.run_if(in_state(GameState::GameOver)),
-            ),
-        );
+        app.init_state::<GameState>()
+            .add_event::<GameFlowEvent>()
+            .add_systems(
+                Update,
+                (game_state_input_events, handle_game_flow_events).chain(),
+            )
+            .add_systems(OnEnter(GameState::Paused), pause_time)
+            .add_systems(OnExit(GameState::Paused), resume_time);
     }
 }
 
 pub fn game_state_input_events(
-    mut next_state: ResMut<NextState<GameState>>,
+    mut flow_events: EventWriter<GameFlowEvent>,
     state: Res<State<GameState>>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
 ) {
     if keyboard_input.just_pressed(KeyCode::Escape) {
         match state.get() {
-            GameState::InGame => next_state.set(GameState::Paused),
-            GameState::Paused => next_state.set(GameState::InGame),
+            GameState::InGame => {
+                flow_events.send(GameFlowEvent::Pause);
+            }
+            GameState::Paused => {
+                flow_events.send(GameFlowEvent::Resume);
+            }
             _ => (),
         }
     }
 }
 
-fn transition_to_in_game(mut next_state: ResMut<NextState<GameState>>) {
-    next_state.set(GameState::InGame);
+fn handle_game_flow_events(
+    mut flow_events: EventReader<GameFlowEvent>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in flow_events.read() {
+        let next = match (state.get(), event) {
+            (GameState::MainMenu, GameFlowEvent::StartGame) => GameState::Loading,
+            (GameState::Loading, GameFlowEvent::LoadingFinished) => GameState::InGame,
+            (GameState::InGame, GameFlowEvent::Pause) => GameState::Paused,
+            (GameState::Paused, GameFlowEvent::Resume) => GameState::InGame,
+            (GameState::InGame, GameFlowEvent::PlayerDefeated) => GameState::GameOver,
+            (GameState::Paused | GameState::GameOver, GameFlowEvent::Retry) => GameState::Loading,
+            (
+                GameState::Loading | GameState::Paused | GameState::GameOver,
+                GameFlowEvent::ReturnToMenu,
+            ) => GameState::MainMenu,
+            // anything else doesn't make sense from the current state so it's ignored
+            _ => continue,
+        };
+        next_state.set(next);
+    }
+}
+
+// Stopping virtual time freezes everything that uses Time, including rapier, while paused
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
 }
@@ -0,0 +1,48 @@
+use crate::{
+    combat::DamageEvent,
+    entities::{Block, Dummy},
+    schedule::InGameSet,
+};
+use bevy::prelude::*;
+
+// Numbers from the current match, shown on the game over screen
+#[derive(Resource, Debug, Default, Clone)]
+pub struct MatchStats {
+    pub time_survived: f32,
+    pub enemies_destroyed: u32,
+    pub damage_dealt: f32,
+    pub damage_taken: f32,
+    pub shots_fired: u32,
+    pub waves_cleared: u32,
+    pub arena_seed: Option<u64>, // the seed of the arena, if it was a generated one
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatchStats>().add_systems(
+            Update,
+            (track_match_time, track_damage).in_set(InGameSet::EntityUpdates),
+        );
+    }
+}
+
+fn track_match_time(mut stats: ResMut<MatchStats>, time: Res<Time>) {
+    stats.time_survived += time.delta_seconds();
+}
+
+fn track_damage(
+    mut stats: ResMut<MatchStats>,
+    mut damage_events: EventReader<DamageEvent>,
+    block_query: Query<(), With<Block>>,
+    dummies_query: Query<(), With<Dummy>>,
+) {
+    for event in damage_events.read() {
+        if block_query.contains(event.target) {
+            stats.damage_taken += event.amount;
+        } else if dummies_query.contains(event.target) {
+            stats.damage_dealt += event.amount;
+        }
+    }
+}
@@ -1,5 +1,18 @@
-use crate::combat::Health;
-use crate::{entities::Bullet, schedule::InGameSet};
+use crate::combat::{Health, Shield, StatusEffects};
+use crate::{
+    ai::AiBrain,
+    arena::{ArenaDefinition, SelectedArena},
+    asset_loader::LoadingProgress,
+    boss::BossPatterns,
+    entities::Bullet,
+    lives::{Lives, RespawnCountdown},
+    round::RoundManager,
+    schedule::InGameSet,
+    settings::GameSettings,
+    states::{GameFlowEvent, GameState},
+    stats::MatchStats,
+    waves::{WaveEnemy, WavePhase, WaveSpawner},
+};
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy_inspector_egui::bevy_egui::EguiContext;
@@ -7,18 +20,303 @@ pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, health_ui.in_set(InGameSet::EntityUpdates));
+        app.add_systems(
+            Update,
+            (health_ui, lives_ui, wave_ui, boss_ui).in_set(InGameSet::EntityUpdates),
+        )
+        .add_systems(Update, main_menu.run_if(in_state(GameState::MainMenu)))
+        .add_systems(Update, loading_screen.run_if(in_state(GameState::Loading)))
+        .add_systems(Update, pause_menu.run_if(in_state(GameState::Paused)))
+        .add_systems(
+            Update,
+            game_over_screen.run_if(in_state(GameState::GameOver)),
+        );
     }
 }
 
 fn health_ui(
     mut primary_window: Query<&mut EguiContext, With<PrimaryWindow>>,
-    entity_health: Query<(Entity, &Health, &Name), Without<Bullet>>,
+    entity_health: Query<(Entity, &Health, &Name, Option<&StatusEffects>), Without<Bullet>>,
 ) {
     for mut context in primary_window.iter_mut() {
         egui::Window::new("Health").show(context.get_mut(), |ui| {
-            for (_, health, name) in entity_health.iter() {
+            for (_, health, name, status_effects) in entity_health.iter() {
                 ui.heading(format!("{:#}'s Health: {:#}", name, health.value));
+                // list any active status effects underneath the health
+                for effect in status_effects
+                    .iter()
+                    .flat_map(|status_effects| status_effects.effects.iter())
+                {
+                    ui.label(format!(
+                        "  {} ({:.1}s)",
+                        effect.kind.name(),
+                        effect.remaining
+                    ));
+                }
+            }
+        });
+    }
+}
+
+fn lives_ui(
+    mut primary_window: Query<&mut EguiContext, With<PrimaryWindow>>,
+    lives: Res<Lives>,
+    countdown: Res<RespawnCountdown>,
+) {
+    for mut context in primary_window.iter_mut() {
+        egui::Window::new("Lives").show(context.get_mut(), |ui| {
+            ui.heading(format!("Spare lives: {}", lives.remaining));
+            if let Some(timer) = countdown.timer.as_ref() {
+                ui.label(format!("Respawning in {:.1}s", timer.remaining_secs()));
+            }
+        });
+    }
+}
+
+fn wave_ui(
+    mut primary_window: Query<&mut EguiContext, With<PrimaryWindow>>,
+    spawner: Res<WaveSpawner>,
+    wave_enemies: Query<(), With<WaveEnemy>>,
+) {
+    for mut context in primary_window.iter_mut() {
+        egui::Window::new("Wave").show(context.get_mut(), |ui| {
+            ui.heading(format!("Wave {}", spawner.wave_number()));
+            match &spawner.phase {
+                WavePhase::Countdown(timer) => {
+                    ui.label(format!("Next wave in {:.1}s", timer.remaining_secs()));
+                }
+                WavePhase::Spawning { remaining, .. } => {
+                    ui.label(format!("Incoming: {remaining}"));
+                    ui.label(format!("Enemies left: {}", wave_enemies.iter().count()));
+                }
+                WavePhase::Fighting => {
+                    ui.label(format!("Enemies left: {}", wave_enemies.iter().count()));
+                }
+            }
+        });
+    }
+}
+
+// a health bar for every boss in the arena, along with which phase it's in
+fn boss_ui(
+    mut primary_window: Query<&mut EguiContext, With<PrimaryWindow>>,
+    boss_query: Query<(&Name, &Health, &AiBrain, &BossPatterns, Has<Shield>)>,
+) {
+    if boss_query.is_empty() {
+        return;
+    }
+    for mut context in primary_window.iter_mut() {
+        egui::Window::new("Boss").show(context.get_mut(), |ui| {
+            for (name, health, brain, patterns, shielded) in boss_query.iter() {
+                ui.heading(format!("{name} - {}", patterns.phase_name()));
+                let fraction = (health.value / brain.max_health.max(f32::EPSILON)).clamp(0.0, 1.0);
+                ui.add(egui::ProgressBar::new(fraction).text(format!("{:.0}", health.value)));
+                if shielded {
+                    ui.label("Shielded!");
+                }
+            }
+        });
+    }
+}
+
+// all of the menu screens are small windows in the middle of the screen
+fn menu_window(title: &str) -> egui::Window<'_> {
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+}
+
+fn main_menu(
+    mut primary_window: Query<&mut EguiContext, With<PrimaryWindow>>,
+    mut flow_events: EventWriter<GameFlowEvent>,
+    mut exit: EventWriter<AppExit>,
+    mut selected_arena: ResMut<SelectedArena>,
+    arenas: Res<Assets<ArenaDefinition>>,
+    mut seed_text: Local<String>,
+) {
+    // the arena files load in the background, so the list fills in once they're ready
+    let mut arenas: Vec<&ArenaDefinition> = arenas.iter().map(|(_, arena)| arena).collect();
+    arenas.sort_by(|first, second| first.name.cmp(&second.name));
+    let selected_name = match &*selected_arena {
+        SelectedArena::File(name) => arenas
+            .iter()
+            .find(|arena| arena.name == *name)
+            .map_or(name.clone(), |arena| arena.display_name.clone()),
+        SelectedArena::Generated { .. } => "Generated".to_string(),
+    };
+
+    for mut context in primary_window.iter_mut() {
+        menu_window("MeetingTwo").show(context.get_mut(), |ui| {
+            egui::ComboBox::from_label("Arena")
+                .selected_text(selected_name.as_str())
+                .show_ui(ui, |ui| {
+                    for arena in arenas.iter() {
+                        ui.selectable_value(
+                            &mut *selected_arena,
+                            SelectedArena::File(arena.name.clone()),
+                            arena.display_name.as_str(),
+                        );
+                    }
+                    let generated = matches!(*selected_arena, SelectedArena::Generated { .. });
+                    if ui.selectable_label(generated, "Generated").clicked() && !generated {
+                        *selected_arena = SelectedArena::Generated {
+                            seed: rand::random(),
+                            symmetric: false,
+                        };
+                    }
+                });
+            // the same seed always builds the same arena, so a good one can be played again. It's
+            // typed in as text since a DragValue goes through an f64 and would round most seeds off
+            if let SelectedArena::Generated { seed, symmetric } = &mut *selected_arena {
+                let typed = seed_text.trim().parse::<u64>().ok();
+                let seed_box = ui
+                    .horizontal(|ui| {
+                        ui.label("Seed");
+                        let seed_box = ui.text_edit_singleline(&mut *seed_text);
+                        if ui.button("New").clicked() {
+                            *seed = rand::random();
+                        }
+                        seed_box
+                    })
+                    .inner;
+                if seed_box.has_focus() {
+                    match typed {
+                        Some(typed) => *seed = typed,
+                        None => {
+                            ui.colored_label(egui::Color32::RED, "Seeds are whole numbers");
+                        }
+                    }
+                } else if typed != Some(*seed) {
+                    *seed_text = seed.to_string();
+                }
+                ui.checkbox(symmetric, "Symmetric");
+            }
+            if ui.button("Start").clicked() {
+                flow_events.send(GameFlowEvent::StartGame);
+            }
+            if ui.button("Quit").clicked() {
+                exit.send(AppExit::Success);
+            }
+        });
+    }
+}
+
+fn loading_screen(
+    mut primary_window: Query<&mut EguiContext, With<PrimaryWindow>>,
+    mut flow_events: EventWriter<GameFlowEvent>,
+    progress: Res<LoadingProgress>,
+) {
+    for mut context in primary_window.iter_mut() {
+        menu_window("Loading").show(context.get_mut(), |ui| {
+            ui.add(
+                egui::ProgressBar::new(progress.fraction())
+                    .text(format!("{} / {} assets", progress.loaded, progress.total)),
+            );
+            if !progress.failed.is_empty() {
+                ui.colored_label(egui::Color32::RED, "Some assets failed to load:");
+                for failure in progress.failed.iter() {
+                    ui.colored_label(egui::Color32::RED, failure);
+                }
+                if ui.button("Main Menu").clicked() {
+                    flow_events.send(GameFlowEvent::ReturnToMenu);
+                }
+            }
+        });
+    }
+}
+
+fn pause_menu(
+    mut primary_window: Query<&mut EguiContext, With<PrimaryWindow>>,
+    mut flow_events: EventWriter<GameFlowEvent>,
+    mut settings: ResMut<GameSettings>,
+    mut show_settings: Local<bool>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let mut show_physics_debug = settings.show_physics_debug;
+    let mut show_inspector = settings.show_inspector;
+    let mut dev_tools = settings.dev_tools;
+
+    for mut context in primary_window.iter_mut() {
+        menu_window("Paused").show(context.get_mut(), |ui| {
+            if ui.button("Resume").clicked() {
+                flow_events.send(GameFlowEvent::Resume);
+            }
+            if ui.button("Restart").clicked() {
+                flow_events.send(GameFlowEvent::Retry);
+            }
+            if ui.button("Settings").clicked() {
+                *show_settings = !*show_settings;
+            }
+            if *show_settings {
+                ui.checkbox(&mut show_physics_debug, "Show physics colliders");
+                ui.checkbox(&mut show_inspector, "Show world inspector");
+                ui.checkbox(&mut dev_tools, "Dev tools (J/K spawn dummies)");
+            }
+            if ui.button("Main Menu").clicked() {
+                flow_events.send(GameFlowEvent::ReturnToMenu);
+            }
+            if ui.button("Quit").clicked() {
+                exit.send(AppExit::Success);
+            }
+        });
+    }
+
+    // only touch the settings when something changed so change detection stays meaningful
+    if show_physics_debug != settings.show_physics_debug {
+        settings.show_physics_debug = show_physics_debug;
+    }
+    if show_inspector != settings.show_inspector {
+        settings.show_inspector = show_inspector;
+    }
+    if dev_tools != settings.dev_tools {
+        settings.dev_tools = dev_tools;
+    }
+}
+
+fn game_over_screen(
+    mut primary_window: Query<&mut EguiContext, With<PrimaryWindow>>,
+    mut flow_events: EventWriter<GameFlowEvent>,
+    stats: Res<MatchStats>,
+    rounds: Res<RoundManager>,
+) {
+    for mut context in primary_window.iter_mut() {
+        menu_window("Game Over").show(context.get_mut(), |ui| {
+            ui.heading(format!("Round {}", rounds.round));
+            ui.label(format!("Time survived: {:.1}s", stats.time_survived));
+            ui.label(format!("Enemies destroyed: {}", stats.enemies_destroyed));
+            ui.label(format!("Damage dealt: {:.0}", stats.damage_dealt));
+            ui.label(format!("Damage taken: {:.0}", stats.damage_taken));
+            ui.label(format!("Shots fired: {}", stats.shots_fired));
+            ui.label(format!("Waves cleared: {}", stats.waves_cleared));
+            // copy the seed to play the same arena again or send it to someone else
+            if let Some(seed) = stats.arena_seed {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Arena seed: {seed}"));
+                    if ui.button("Copy").clicked() {
+                        ui.output_mut(|output| output.copied_text = seed.to_string());
+                    }
+                });
+            }
+            if !rounds.history.is_empty() {
+                ui.collapsing("Previous rounds", |ui| {
+                    for previous in rounds.history.iter() {
+                        ui.label(format!(
+                            "Round {}: {:.1}s, {} destroyed, {:.0} damage dealt",
+                            previous.round,
+                            previous.stats.time_survived,
+                            previous.stats.enemies_destroyed,
+                            previous.stats.damage_dealt
+                        ));
+                    }
+                });
+            }
+            ui.separator();
+            if ui.button("Retry").clicked() {
+                flow_events.send(GameFlowEvent::Retry);
+            }
+            if ui.button("Main Menu").clicked() {
+                flow_events.send(GameFlowEvent::ReturnToMenu);
             }
         });
     }
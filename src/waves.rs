@@ -0,0 +1,243 @@
+use crate::{
+    archetypes::{find_archetype, EnemyArchetype},
+    arena::{ArenaBounds, CurrentArena},
+    prefabs::{PrefabOverrides, PrefabSpawner},
+    schedule::InGameSet,
+    states::GameState,
+    stats::MatchStats,
+};
+use bevy::prelude::*;
+use rand::Rng;
+
+// how far from the arena's edge enemies have to appear
+const SPAWN_MARGIN: f32 = 1.5;
+// every time all of the waves have been cleared they start again from the first one with this much
+// more enemies and health per loop
+const COUNT_SCALE_PER_CYCLE: f32 = 0.5;
+const HEALTH_SCALE_PER_CYCLE: f32 = 0.25;
+
+// A rectangle on the ground enemies can appear in, every arena names its own (see arena/definition.rs)
+#[derive(Debug, Clone)]
+pub struct SpawnZone {
+    pub centre: Vec3,
+    pub half_size: Vec2, // half the width (x) and depth (z) of the zone
+}
+
+impl SpawnZone {
+    pub fn random_point(&self, rng: &mut impl Rng) -> Vec3 {
+        self.centre
+            + Vec3::new(
+                rng.gen_range(-self.half_size.x..=self.half_size.x),
+                0.0,
+                rng.gen_range(-self.half_size.y..=self.half_size.y),
+            )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WaveDefinition {
+    pub enemy: String, // name of the enemy archetype, see assets/enemies
+    pub count: u32,
+    pub spawn_interval: f32,      // seconds between each enemy in the wave
+    pub spawn_zones: Vec<String>, // names of the arena's spawn zones the wave can appear in
+    pub delay_before: f32,        // seconds of quiet before the wave starts
+}
+
+#[derive(Debug)]
+pub enum WavePhase {
+    Countdown(Timer),
+    Spawning { remaining: u32, timer: Timer },
+    Fighting, // everything has spawned, waiting for the wave to be cleared
+}
+
+#[derive(Resource, Debug)]
+pub struct WaveSpawner {
+    pub waves: Vec<WaveDefinition>,
+    pub current: usize,
+    pub cycle: u32, // how many times we've been through every wave, used for difficulty scaling
+    pub phase: WavePhase,
+}
+
+impl WaveSpawner {
+    pub fn new(waves: Vec<WaveDefinition>) -> Self {
+        let first_delay = waves.first().map_or(0.0, |wave| wave.delay_before);
+        Self {
+            waves,
+            current: 0,
+            cycle: 0,
+            phase: WavePhase::Countdown(Timer::from_seconds(first_delay, TimerMode::Once)),
+        }
+    }
+
+    // the number of the wave being played counting every cycle, starting from 1
+    pub fn wave_number(&self) -> usize {
+        self.cycle as usize * self.waves.len() + self.current + 1
+    }
+
+    fn scaled_count(&self, wave: &WaveDefinition) -> u32 {
+        (wave.count as f32 * (1.0 + COUNT_SCALE_PER_CYCLE * self.cycle as f32)).round() as u32
+    }
+
+    fn health_scale(&self) -> f32 {
+        1.0 + HEALTH_SCALE_PER_CYCLE * self.cycle as f32
+    }
+}
+
+impl Default for WaveSpawner {
+    fn default() -> Self {
+        let north = "north".to_string();
+        let south = "south".to_string();
+        Self::new(vec![
+            WaveDefinition {
+                enemy: "roaming_dummy".to_string(),
+                count: 3,
+                spawn_interval: 1.0,
+                spawn_zones: vec![north.clone()],
+                delay_before: 3.0,
+            },
+            WaveDefinition {
+                enemy: "roaming_dummy".to_string(),
+                count: 5,
+                spawn_interval: 0.75,
+                spawn_zones: vec![north.clone(), south.clone()],
+                delay_before: 5.0,
+            },
+            WaveDefinition {
+                enemy: "fixed_dummy".to_string(),
+                count: 2,
+                spawn_interval: 2.0,
+                spawn_zones: vec![south.clone()],
+                delay_before: 5.0,
+            },
+            WaveDefinition {
+                enemy: "gunner_dummy".to_string(),
+                count: 3,
+                spawn_interval: 1.5,
+                spawn_zones: vec![north.clone(), south.clone()],
+                delay_before: 5.0,
+            },
+            WaveDefinition {
+                enemy: "roaming_dummy".to_string(),
+                count: 8,
+                spawn_interval: 0.5,
+                spawn_zones: vec![north.clone(), south.clone()],
+                delay_before: 5.0,
+            },
+            // a couple of hundred tiny robots flocking together, the rocket launcher helps here
+            WaveDefinition {
+                enemy: "swarm_dummy".to_string(),
+                count: 200,
+                spawn_interval: 0.02,
+                spawn_zones: vec![north.clone(), south],
+                delay_before: 5.0,
+            },
+            // the boss gets a bit longer to get ready for
+            WaveDefinition {
+                enemy: "boss_dummy".to_string(),
+                count: 1,
+                spawn_interval: 1.0,
+                spawn_zones: vec![north],
+                delay_before: 8.0,
+            },
+        ])
+    }
+}
+
+// marks enemies that belong to the current wave, the wave is cleared once none are left
+#[derive(Component, Debug)]
+pub struct WaveEnemy;
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WaveSpawner>()
+            .add_systems(OnExit(GameState::Loading), reset_waves)
+            .add_systems(Update, run_waves.in_set(InGameSet::EntityUpdates));
+    }
+}
+
+fn reset_waves(mut spawner: ResMut<WaveSpawner>) {
+    *spawner = WaveSpawner::default();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_waves(
+    mut prefabs: PrefabSpawner,
+    mut spawner: ResMut<WaveSpawner>,
+    mut stats: ResMut<MatchStats>,
+    archetypes: Res<Assets<EnemyArchetype>>,
+    bounds: Res<ArenaBounds>,
+    arena: Res<CurrentArena>,
+    wave_enemies: Query<(), With<WaveEnemy>>,
+    time: Res<Time>,
+) {
+    if spawner.waves.is_empty() {
+        return;
+    }
+    let wave = spawner.waves[spawner.current].clone();
+    let count = spawner.scaled_count(&wave);
+    let health_scale = spawner.health_scale();
+
+    match &mut spawner.phase {
+        WavePhase::Countdown(timer) => {
+            if timer.tick(time.delta()).finished() {
+                // the first enemy comes in straight away, the rest follow on the interval
+                let mut timer = Timer::from_seconds(wave.spawn_interval, TimerMode::Repeating);
+                timer.set_elapsed(timer.duration());
+                spawner.phase = WavePhase::Spawning {
+                    remaining: count,
+                    timer,
+                };
+            }
+        }
+        WavePhase::Spawning { remaining, timer } => {
+            timer.tick(time.delta());
+            let due = timer.times_finished_this_tick().min(*remaining);
+            let mut rng = rand::thread_rng();
+            let zones: Vec<SpawnZone> = wave
+                .spawn_zones
+                .iter()
+                .filter_map(|name| {
+                    let zone = arena.0.spawn_zone(name);
+                    if zone.is_none() {
+                        warn_once!("arena {} has no spawn zone called {name}", arena.0.name);
+                    }
+                    zone
+                })
+                .collect();
+            for _ in 0..due {
+                if zones.is_empty() {
+                    break;
+                }
+                let zone = &zones[rng.gen_range(0..zones.len())];
+                // a zone hanging over the edge of the arena only spawns in the part that's inside
+                let position = bounds.clamp(zone.random_point(&mut rng), SPAWN_MARGIN);
+                let overrides = PrefabOverrides {
+                    health: find_archetype(&archetypes, &wave.enemy)
+                        .map(|archetype| archetype.health * health_scale),
+                    ..PrefabOverrides::at(position)
+                };
+                if let Some(mut enemy) = prefabs.spawn(&wave.enemy, overrides) {
+                    enemy.insert(WaveEnemy);
+                }
+            }
+            *remaining -= due;
+            if *remaining == 0 {
+                spawner.phase = WavePhase::Fighting;
+            }
+        }
+        WavePhase::Fighting => {
+            if wave_enemies.is_empty() {
+                stats.waves_cleared += 1;
+                spawner.current += 1;
+                if spawner.current == spawner.waves.len() {
+                    spawner.current = 0;
+                    spawner.cycle += 1;
+                }
+                let delay = spawner.waves[spawner.current].delay_before;
+                spawner.phase = WavePhase::Countdown(Timer::from_seconds(delay, TimerMode::Once));
+            }
+        }
+    }
+}